@@ -0,0 +1,203 @@
+//! Reading release archives that have already been downloaded and verified.
+
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use zed_extension_api::Result;
+
+/// The archive formats github-mcp-server releases are published in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    GzipTar,
+    Zip,
+}
+
+/// Returns every regular file in `archive`, with its path relative to the install directory.
+pub fn files(archive: &[u8], kind: ArchiveKind) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let files = match kind {
+        ArchiveKind::GzipTar => tar_entries(GzDecoder::new(archive))?,
+        ArchiveKind::Zip => zip_entries(archive)?,
+    };
+
+    files
+        .into_iter()
+        .map(|(name, contents)| Ok((entry_path(&name)?, contents)))
+        .collect()
+}
+
+/// Converts an archive entry name to a relative path, rejecting entries that would escape the
+/// install directory.
+fn entry_path(name: &str) -> Result<PathBuf> {
+    let relative = Path::new(name);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!(
+            "archive entry '{name}' escapes the install directory"
+        ));
+    }
+    Ok(relative.to_path_buf())
+}
+
+fn tar_entries(tar: impl Read) -> Result<Vec<(String, Vec<u8>)>> {
+    let error = |e: std::io::Error| format!("failed to read tar archive: {e}");
+
+    let mut entries = Vec::new();
+    for entry in tar::Archive::new(tar).entries().map_err(error)? {
+        let mut entry = entry.map_err(error)?;
+        // Regular files only; directories are created on demand and anything else
+        // (links, devices) is not expected in a release archive.
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // Includes GNU long names and pax paths, which the header's name field can't hold.
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(error)?;
+        entries.push((name, contents));
+    }
+
+    Ok(entries)
+}
+
+fn zip_entries(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let error = |e: zip::result::ZipError| format!("failed to read zip archive: {e}");
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip)).map_err(error)?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("failed to decompress zip entry '{name}': {e}"))?;
+        entries.push((name, contents));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written with Python's `zipfile` and `tarfile`, not by this module.
+    const RELEASE_ZIP: &[u8] = include_bytes!("testdata/release.zip");
+    const TRAVERSAL_ZIP: &[u8] = include_bytes!("testdata/traversal.zip");
+    const BZIP2_ZIP: &[u8] = include_bytes!("testdata/bzip2.zip");
+    const RELEASE_TAR_GZ: &[u8] = include_bytes!("testdata/release.tar.gz");
+    const PREFIX_TAR: &[u8] = include_bytes!("testdata/prefix.tar");
+    const GNU_LONG_NAME_TAR: &[u8] = include_bytes!("testdata/gnu_long_name.tar");
+    const PAX_PATH_TAR: &[u8] = include_bytes!("testdata/pax_path.tar");
+
+    fn names(files: &[(PathBuf, Vec<u8>)]) -> Vec<String> {
+        files
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_zip_entries() {
+        let files = files(RELEASE_ZIP, ArchiveKind::Zip).unwrap();
+
+        assert_eq!(
+            names(&files),
+            ["github-mcp-server.exe", "README.md", "docs/guide.md"]
+        );
+        assert_eq!(files[0].1, b"MZ fake windows binary\n".repeat(20));
+        assert_eq!(files[1].1, b"# github-mcp-server\n");
+        assert_eq!(files[2].1, b"guide\n".repeat(10));
+    }
+
+    #[test]
+    fn rejects_zip_entries_escaping_install_dir() {
+        let error = files(TRAVERSAL_ZIP, ArchiveKind::Zip).unwrap_err();
+
+        assert_eq!(
+            error,
+            "archive entry '../evil.exe' escapes the install directory"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_zip_compression() {
+        let error = files(BZIP2_ZIP, ArchiveKind::Zip).unwrap_err();
+
+        assert!(
+            error.contains("Compression method not supported"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_zip() {
+        let error = files(&RELEASE_ZIP[..RELEASE_ZIP.len() / 2], ArchiveKind::Zip).unwrap_err();
+
+        assert!(error.starts_with("failed to read zip archive"), "{error}");
+        let data_cut_off = [&RELEASE_ZIP[..40], &RELEASE_ZIP[RELEASE_ZIP.len() - 300..]].concat();
+        assert!(files(&data_cut_off, ArchiveKind::Zip).is_err());
+    }
+
+    #[test]
+    fn reads_regular_files_from_tar_gz() {
+        let files = files(RELEASE_TAR_GZ, ArchiveKind::GzipTar).unwrap();
+
+        assert_eq!(names(&files), ["github-mcp-server", "docs/guide.md"]);
+        assert_eq!(files[0].1, b"#!/bin/sh\n");
+    }
+
+    #[test]
+    fn joins_ustar_prefix_and_name() {
+        let entries = tar_entries(PREFIX_TAR).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].0,
+            format!("{}/{}/github-mcp-server", "a".repeat(60), "b".repeat(60))
+        );
+    }
+
+    #[test]
+    fn reads_gnu_long_names_and_pax_paths() {
+        let entries = tar_entries(GNU_LONG_NAME_TAR).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].0,
+            format!("{}/github-mcp-server", "c".repeat(120))
+        );
+        assert_eq!(entries[0].1, b"#!/bin/sh\n");
+
+        let entries = tar_entries(PAX_PATH_TAR).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].0,
+            format!("rélease/{}/github-mcp-server", "d".repeat(200))
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_tar() {
+        // The header and part of the entry's contents.
+        let error = tar_entries(&PREFIX_TAR[..512 + 4]).unwrap_err();
+
+        assert!(error.starts_with("failed to read tar archive"), "{error}");
+    }
+
+    #[test]
+    fn rejects_paths_escaping_install_dir() {
+        for name in ["../evil", "bin/../../evil", "/etc/passwd"] {
+            assert!(entry_path(name).is_err(), "{name}");
+        }
+        for name in ["github-mcp-server", "./bin/github-mcp-server", "a..b"] {
+            assert!(entry_path(name).is_ok(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Choosing the release asset to install on the current platform.
+//!
+//! Asset names are matched loosely, so that upstream can rename archives, switch between
+//! `x86_64` and `amd64`, or publish raw binaries without breaking installation.
+
+use crate::archive::ArchiveKind;
+use crate::BINARY_NAME;
+use zed_extension_api::{self as zed, GithubReleaseAsset, Result};
+
+const LINUX_ALIASES: &[&str] = &["linux"];
+const MAC_ALIASES: &[&str] = &["darwin", "macos", "mac", "osx", "apple"];
+const WINDOWS_ALIASES: &[&str] = &["windows", "win", "win64", "win32"];
+
+// `x86_64` and `x86-64` are normalized to `amd64` before matching, see `tokens`.
+const X86_64_ALIASES: &[&str] = &["amd64", "x64"];
+const AARCH64_ALIASES: &[&str] = &["arm64", "aarch64"];
+const X86_ALIASES: &[&str] = &["i386", "i686", "386", "x86"];
+
+/// Marks macOS builds that run on every architecture.
+const UNIVERSAL_ALIASES: &[&str] = &["universal", "all"];
+
+/// How an asset is installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Archive(ArchiveKind),
+    /// The asset is the executable itself.
+    Binary,
+}
+
+/// Returns the asset that best matches `platform` and `arch`, and how to install it.
+///
+/// Assets for another OS or architecture, and formats that can't be installed (checksums,
+/// signatures, packages, `.tar.xz`), are never chosen. Among the rest, the platform's usual
+/// archive format beats other archives, which beat raw binaries.
+pub fn select(
+    assets: &[GithubReleaseAsset],
+    platform: zed::Os,
+    arch: zed::Architecture,
+) -> Result<(&GithubReleaseAsset, AssetKind)> {
+    assets
+        .iter()
+        .filter_map(|asset| {
+            let (score, kind) = score(&asset.name, platform, arch)?;
+            Some((score, asset, kind))
+        })
+        // `max_by_key` keeps the last of equal scores, so compare in reverse to keep the first.
+        .rev()
+        .max_by_key(|(score, _, _)| *score)
+        .map(|(_, asset, kind)| (asset, kind))
+        .ok_or_else(|| {
+            let names: Vec<_> = assets.iter().map(|asset| asset.name.as_str()).collect();
+            let has_xz = names
+                .iter()
+                .any(|name| name.to_ascii_lowercase().ends_with(".tar.xz"));
+            format!(
+                "no asset for {} {}{}, available assets: {}",
+                os_name(platform),
+                arch_name(arch),
+                if has_xz {
+                    " (`.tar.xz` archives are not supported)"
+                } else {
+                    ""
+                },
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+}
+
+/// Scores how well `name` fits the platform, or returns `None` if it can't be used there.
+fn score(name: &str, platform: zed::Os, arch: zed::Architecture) -> Option<(u32, AssetKind)> {
+    let kind = asset_kind(name, platform)?;
+    let tokens = tokens(name);
+    let has_any = |aliases: &[&str]| tokens.iter().any(|token| aliases.contains(&token.as_str()));
+
+    let (os_aliases, other_os_aliases) = match platform {
+        zed::Os::Linux => (LINUX_ALIASES, [MAC_ALIASES, WINDOWS_ALIASES]),
+        zed::Os::Mac => (MAC_ALIASES, [LINUX_ALIASES, WINDOWS_ALIASES]),
+        zed::Os::Windows => (WINDOWS_ALIASES, [LINUX_ALIASES, MAC_ALIASES]),
+    };
+    if !has_any(os_aliases) || other_os_aliases.iter().any(|aliases| has_any(aliases)) {
+        return None;
+    }
+
+    let (arch_aliases, other_arch_aliases) = match arch {
+        zed::Architecture::X8664 => (X86_64_ALIASES, [AARCH64_ALIASES, X86_ALIASES]),
+        zed::Architecture::Aarch64 => (AARCH64_ALIASES, [X86_64_ALIASES, X86_ALIASES]),
+        zed::Architecture::X86 => (X86_ALIASES, [X86_64_ALIASES, AARCH64_ALIASES]),
+    };
+    let arch_score = if has_any(arch_aliases) {
+        2
+    } else if platform == zed::Os::Mac && has_any(UNIVERSAL_ALIASES) {
+        1
+    } else {
+        return None;
+    };
+    if other_arch_aliases.iter().any(|aliases| has_any(aliases)) {
+        return None;
+    }
+
+    let kind_score = match (kind, platform) {
+        (AssetKind::Archive(ArchiveKind::Zip), zed::Os::Windows) => 3,
+        (AssetKind::Archive(ArchiveKind::GzipTar), zed::Os::Mac | zed::Os::Linux) => 3,
+        (AssetKind::Archive(_), _) => 2,
+        (AssetKind::Binary, _) => 1,
+    };
+    let name_score = u32::from(name.to_ascii_lowercase().starts_with(BINARY_NAME));
+
+    Some((kind_score * 10 + arch_score * 2 + name_score, kind))
+}
+
+/// Determines how an asset would be installed from its name, if it can be at all.
+fn asset_kind(name: &str, platform: zed::Os) -> Option<AssetKind> {
+    let name = name.to_ascii_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        return Some(AssetKind::Archive(ArchiveKind::GzipTar));
+    }
+    if name.ends_with(".zip") {
+        return Some(AssetKind::Archive(ArchiveKind::Zip));
+    }
+    if platform == zed::Os::Windows {
+        return name.ends_with(".exe").then_some(AssetKind::Binary);
+    }
+
+    // A word after the last dot that isn't part of a version number is an extension
+    // (`.txt`, `.sig`, `.deb`, `.xz`, ...) of something other than an executable.
+    match name.rsplit_once('.') {
+        Some((_, extension))
+            if extension.chars().all(|c| c.is_ascii_alphanumeric())
+                && !extension.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            None
+        }
+        _ => Some(AssetKind::Binary),
+    }
+}
+
+/// Splits an asset name into lowercase words, keeping `x86_64` together as `amd64`.
+fn tokens(name: &str) -> Vec<String> {
+    name.to_ascii_lowercase()
+        .replace("x86_64", "amd64")
+        .replace("x86-64", "amd64")
+        .split(['_', '-', '.'])
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn os_name(platform: zed::Os) -> &'static str {
+    match platform {
+        zed::Os::Mac => "macOS",
+        zed::Os::Linux => "Linux",
+        zed::Os::Windows => "Windows",
+    }
+}
+
+fn arch_name(arch: zed::Architecture) -> &'static str {
+    match arch {
+        zed::Architecture::Aarch64 => "arm64",
+        zed::Architecture::X86 => "x86",
+        zed::Architecture::X8664 => "x86_64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed::{Architecture, Os};
+
+    fn assets(names: &[&str]) -> Vec<GithubReleaseAsset> {
+        names
+            .iter()
+            .map(|name| GithubReleaseAsset {
+                name: name.to_string(),
+                download_url: format!("https://example.com/{name}"),
+            })
+            .collect()
+    }
+
+    fn selected(names: &[&str], platform: Os, arch: Architecture) -> Option<(String, AssetKind)> {
+        let assets = assets(names);
+        select(&assets, platform, arch)
+            .ok()
+            .map(|(asset, kind)| (asset.name.clone(), kind))
+    }
+
+    const CURRENT: &[&str] = &[
+        "github-mcp-server_Darwin_arm64.tar.gz",
+        "github-mcp-server_Darwin_x86_64.tar.gz",
+        "github-mcp-server_Linux_arm64.tar.gz",
+        "github-mcp-server_Linux_i386.tar.gz",
+        "github-mcp-server_Linux_x86_64.tar.gz",
+        "github-mcp-server_Windows_arm64.zip",
+        "github-mcp-server_Windows_i386.zip",
+        "github-mcp-server_Windows_x86_64.zip",
+        "github-mcp-server_0.5.0_checksums.txt",
+    ];
+
+    #[test]
+    fn matches_current_naming_scheme() {
+        let gzip_tar = AssetKind::Archive(ArchiveKind::GzipTar);
+        let zip = AssetKind::Archive(ArchiveKind::Zip);
+        for (platform, arch, name, kind) in [
+            (
+                Os::Linux,
+                Architecture::X8664,
+                "github-mcp-server_Linux_x86_64.tar.gz",
+                gzip_tar,
+            ),
+            (
+                Os::Linux,
+                Architecture::Aarch64,
+                "github-mcp-server_Linux_arm64.tar.gz",
+                gzip_tar,
+            ),
+            (
+                Os::Linux,
+                Architecture::X86,
+                "github-mcp-server_Linux_i386.tar.gz",
+                gzip_tar,
+            ),
+            (
+                Os::Mac,
+                Architecture::Aarch64,
+                "github-mcp-server_Darwin_arm64.tar.gz",
+                gzip_tar,
+            ),
+            (
+                Os::Mac,
+                Architecture::X8664,
+                "github-mcp-server_Darwin_x86_64.tar.gz",
+                gzip_tar,
+            ),
+            (
+                Os::Windows,
+                Architecture::X8664,
+                "github-mcp-server_Windows_x86_64.zip",
+                zip,
+            ),
+            (
+                Os::Windows,
+                Architecture::X86,
+                "github-mcp-server_Windows_i386.zip",
+                zip,
+            ),
+        ] {
+            assert_eq!(
+                selected(CURRENT, platform, arch),
+                Some((name.to_string(), kind)),
+                "{platform:?} {arch:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_os_and_arch_aliases() {
+        let names = &[
+            "github-mcp-server-v1.0.0-macos-aarch64.tar.gz",
+            "github-mcp-server-v1.0.0-linux-amd64.tgz",
+            "github-mcp-server-v1.0.0-win-x64.zip",
+        ];
+        assert_eq!(
+            selected(names, Os::Mac, Architecture::Aarch64).unwrap().0,
+            names[0]
+        );
+        assert_eq!(
+            selected(names, Os::Linux, Architecture::X8664).unwrap().0,
+            names[1]
+        );
+        assert_eq!(
+            selected(names, Os::Windows, Architecture::X8664).unwrap().0,
+            names[2]
+        );
+    }
+
+    #[test]
+    fn prefers_supported_archives_over_raw_binaries_and_xz() {
+        let names = &[
+            "github-mcp-server_linux_amd64",
+            "github-mcp-server_linux_amd64.tar.xz",
+            "github-mcp-server_linux_amd64.tar.gz",
+        ];
+        assert_eq!(
+            selected(names, Os::Linux, Architecture::X8664),
+            Some((
+                names[2].to_string(),
+                AssetKind::Archive(ArchiveKind::GzipTar)
+            ))
+        );
+        assert_eq!(
+            selected(&names[..2], Os::Linux, Architecture::X8664),
+            Some((names[0].to_string(), AssetKind::Binary))
+        );
+    }
+
+    #[test]
+    fn installs_raw_binaries() {
+        let names = &[
+            "github-mcp-server_1.2.0_linux_arm64",
+            "github-mcp-server_1.2.0_windows_arm64.exe",
+        ];
+        assert_eq!(
+            selected(names, Os::Linux, Architecture::Aarch64),
+            Some((names[0].to_string(), AssetKind::Binary))
+        );
+        assert_eq!(
+            selected(names, Os::Windows, Architecture::Aarch64),
+            Some((names[1].to_string(), AssetKind::Binary))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_universal_mac_builds() {
+        let names = &["github-mcp-server_darwin_universal.tar.gz"];
+        assert!(selected(names, Os::Mac, Architecture::Aarch64).is_some());
+        assert!(selected(names, Os::Linux, Architecture::X8664).is_none());
+    }
+
+    #[test]
+    fn never_selects_other_platforms_or_metadata() {
+        let names = &[
+            "github-mcp-server_Linux_arm64.tar.gz",
+            "github-mcp-server_Linux_x86_64.tar.gz.sig",
+            "github-mcp-server_Linux_x86_64.sbom.json",
+            "github-mcp-server_linux_amd64.deb",
+            "github-mcp-server_Darwin_x86_64.tar.gz",
+        ];
+        assert_eq!(selected(names, Os::Linux, Architecture::X8664), None);
+    }
+
+    #[test]
+    fn lists_available_assets_when_nothing_matches() {
+        let assets = assets(&["github-mcp-server_Linux_arm64.tar.gz", "checksums.txt"]);
+        let error = select(&assets, Os::Windows, Architecture::X8664).unwrap_err();
+        assert_eq!(
+            error,
+            "no asset for Windows x86_64, available assets: \
+             github-mcp-server_Linux_arm64.tar.gz, checksums.txt"
+        );
+        assert_eq!(
+            select(&[], Os::Linux, Architecture::X8664).unwrap_err(),
+            "no asset for Linux x86_64, available assets: none"
+        );
+    }
+
+    #[test]
+    fn explains_that_xz_archives_are_not_supported() {
+        let assets = assets(&["github-mcp-server_linux_amd64.tar.xz"]);
+        let error = select(&assets, Os::Linux, Architecture::X8664).unwrap_err();
+        assert_eq!(
+            error,
+            "no asset for Linux x86_64 (`.tar.xz` archives are not supported), available \
+             assets: github-mcp-server_linux_amd64.tar.xz"
+        );
+    }
+
+    #[test]
+    fn does_not_mistake_x32_for_x86() {
+        let names = &["github-mcp-server_linux_x32.tar.gz"];
+        assert_eq!(selected(names, Os::Linux, Architecture::X86), None);
+        assert_eq!(selected(names, Os::Linux, Architecture::X8664), None);
+    }
+}
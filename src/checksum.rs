@@ -0,0 +1,75 @@
+//! SHA-256 verification of downloaded release archives.
+
+use sha2::{Digest, Sha256};
+
+/// Returns the lowercase hex SHA-256 digest of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Looks up the digest for `asset_name` in a `sha256sum`-style checksums file.
+pub fn expected_digest(checksums: &str, asset_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (digest, file_name) = line.trim().split_once(char::is_whitespace)?;
+        // `sha256sum` marks binary-mode entries with a leading `*`.
+        let file_name = file_name.trim_start().trim_start_matches('*');
+        (file_name == asset_name).then(|| digest.to_ascii_lowercase())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_nist_test_vectors() {
+        for (message, digest) in [
+            (
+                "",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (
+                "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopq\
+                 klmnopqrlmnopqrsmnopqrstnopqrstu",
+                "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+            ),
+        ] {
+            assert_eq!(sha256_hex(message.as_bytes()), digest, "{message}");
+        }
+        assert_eq!(
+            sha256_hex(&[b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn finds_digest_in_checksums_file() {
+        let checksums = "\
+            0123ABCD  github-mcp-server_Linux_x86_64.tar.gz\n\
+            4567cdef *github-mcp-server_Windows_x86_64.zip\n";
+
+        assert_eq!(
+            expected_digest(checksums, "github-mcp-server_Linux_x86_64.tar.gz").as_deref(),
+            Some("0123abcd")
+        );
+        assert_eq!(
+            expected_digest(checksums, "github-mcp-server_Windows_x86_64.zip").as_deref(),
+            Some("4567cdef")
+        );
+        assert_eq!(
+            expected_digest(checksums, "github-mcp-server_Linux_x86_64"),
+            None
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Authentication as a GitHub App installation.
+//!
+//! The app's private key signs a short-lived JWT, which is exchanged for an installation
+//! token through the REST API. Installation tokens expire after an hour, so they are cached
+//! with their expiry and replaced shortly before it.
+
+use crate::host::Host;
+use crate::{checksum, preflight, token, unix_time};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::rand_core::OsRng;
+use rsa::signature::{RandomizedSigner, SignatureEncoding};
+use rsa::RsaPrivateKey;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use zed_extension_api::http_client::HttpMethod;
+use zed_extension_api::{serde_json, Result};
+
+const INSTALLATION_TOKENS_FILE: &str = "installation_tokens.json";
+
+/// Installation tokens with less than this left are replaced, so a server started with one
+/// keeps working for a while.
+const REFRESH_MARGIN_SECS: u64 = 10 * 60;
+
+/// GitHub rejects JWTs that are valid for more than ten minutes.
+const JWT_LIFETIME_SECS: u64 = 9 * 60;
+
+/// Backdates the JWT to allow for the host's clock running ahead of GitHub's.
+const CLOCK_DRIFT_SECS: u64 = 60;
+
+/// A GitHub App installation to authenticate as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubApp {
+    pub app_id: u64,
+    pub installation_id: u64,
+    pub private_key_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedToken {
+    token: String,
+    expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallationTokenResponse {
+    token: String,
+    expires_at: String,
+}
+
+/// Returns an installation token for `app` from the GitHub API at `api_url`, creating a new
+/// one if the cached token is about to expire.
+pub fn installation_token(host: &dyn Host, api_url: &str, app: &GitHubApp) -> Result<String> {
+    let cache_key = cache_key(api_url, app);
+    let mut cached: HashMap<String, CachedToken> = host
+        .read_file(INSTALLATION_TOKENS_FILE)
+        .ok()
+        .and_then(|cached| serde_json::from_slice(&cached).ok())
+        .unwrap_or_default();
+    let now = unix_time();
+    if let Some(cached) = cached
+        .get(&cache_key)
+        .filter(|cached| cached.expires_at > now + REFRESH_MARGIN_SECS)
+    {
+        return Ok(cached.token.clone());
+    }
+
+    let private_key = token::read_file(&app.private_key_path)
+        .and_then(|pem| private_key(&pem))
+        .map_err(|e| {
+            format!(
+                "failed to read the GitHub App private key from `private_key_path` ({}): {e}",
+                app.private_key_path
+            )
+        })?;
+    let jwt = jwt(app.app_id, &private_key, now)?;
+
+    let url = format!(
+        "{api_url}/app/installations/{}/access_tokens",
+        app.installation_id
+    );
+    let authorization = format!("Bearer {jwt}");
+    let response = host
+        .api_request(
+            HttpMethod::Post,
+            &url,
+            &[("Authorization", authorization.as_str())],
+        )
+        .map_err(|e| {
+            format!(
+                "failed to create a GitHub App installation token (POST {url}): {e}. Check \
+                 `app_id`, `installation_id` and `private_key_path`"
+            )
+        })?;
+    let response: InstallationTokenResponse = serde_json::from_slice(&response.body)
+        .map_err(|e| format!("unexpected response from POST {url}: {e}"))?;
+
+    let expires_at = preflight::parse_expiration(&response.expires_at).ok_or_else(|| {
+        format!(
+            "unexpected installation token expiry '{}'",
+            response.expires_at
+        )
+    })?;
+    cached.retain(|_, cached| cached.expires_at > now);
+    cached.insert(
+        cache_key,
+        CachedToken {
+            token: response.token.clone(),
+            expires_at,
+        },
+    );
+    if let Ok(contents) = serde_json::to_vec(&cached) {
+        host.write_file(INSTALLATION_TOKENS_FILE, &contents).ok();
+    }
+
+    Ok(response.token)
+}
+
+fn cache_key(api_url: &str, app: &GitHubApp) -> String {
+    checksum::sha256_hex(format!("{api_url}\n{}\n{}", app.app_id, app.installation_id).as_bytes())
+}
+
+/// Parses a PKCS#1 (`BEGIN RSA PRIVATE KEY`) or unencrypted PKCS#8 (`BEGIN PRIVATE KEY`)
+/// PEM file, as downloaded from a GitHub App's settings.
+fn private_key(pem: &str) -> Result<RsaPrivateKey> {
+    if pem.contains("BEGIN ENCRYPTED PRIVATE KEY") {
+        return Err("encrypted private keys are not supported".to_string());
+    }
+    if pem.contains("BEGIN RSA PRIVATE KEY") {
+        RsaPrivateKey::from_pkcs1_pem(pem).map_err(|e| e.to_string())
+    } else {
+        RsaPrivateKey::from_pkcs8_pem(pem).map_err(|e| e.to_string())
+    }
+}
+
+/// Returns a JWT identifying the app, signed with its private key (RS256).
+fn jwt(app_id: u64, private_key: &RsaPrivateKey, now: u64) -> Result<String> {
+    let header = base64_url_encode(br#"{"alg":"RS256","typ":"JWT"}"#);
+    let claims = serde_json::json!({
+        "iat": now.saturating_sub(CLOCK_DRIFT_SECS),
+        "exp": now + JWT_LIFETIME_SECS,
+        "iss": app_id.to_string(),
+    });
+    let claims = base64_url_encode(claims.to_string().as_bytes());
+    let signing_input = format!("{header}.{claims}");
+    // Signing with a random number generator blinds the private key operation.
+    let signature = SigningKey::<Sha256>::new(private_key.clone())
+        .try_sign_with_rng(&mut OsRng, signing_input.as_bytes())
+        .map_err(|e| format!("failed to sign the GitHub App JWT: {e}"))?;
+    Ok(format!(
+        "{signing_input}.{}",
+        base64_url_encode(&signature.to_bytes())
+    ))
+}
+
+/// Encodes `data` as unpadded base64url.
+fn base64_url_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..=chunk.len() {
+            encoded.push(char::from(
+                ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize],
+            ));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::fake::FakeHost;
+
+    const API_URL: &str = "https://api.github.com";
+    const ACCESS_TOKENS_URL: &str = "https://api.github.com/app/installations/67890/access_tokens";
+
+    fn app() -> GitHubApp {
+        GitHubApp {
+            app_id: 12345,
+            installation_id: 67890,
+            private_key_path: concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/testdata/rsa_1024_pkcs1.pem"
+            )
+            .to_string(),
+        }
+    }
+
+    fn cache(host: &FakeHost, tokens: &[(&str, &str, u64)]) {
+        let cached: HashMap<_, _> = tokens
+            .iter()
+            .map(|(key, token, expires_at)| {
+                let token = CachedToken {
+                    token: token.to_string(),
+                    expires_at: *expires_at,
+                };
+                (key.to_string(), token)
+            })
+            .collect();
+        host.write_file(
+            INSTALLATION_TOKENS_FILE,
+            &serde_json::to_vec(&cached).unwrap(),
+        )
+        .unwrap();
+    }
+
+    fn cached(host: &FakeHost) -> HashMap<String, CachedToken> {
+        serde_json::from_slice(&host.read_file(INSTALLATION_TOKENS_FILE).unwrap()).unwrap()
+    }
+
+    fn serve_token(host: &FakeHost, token: &str) {
+        host.state().responses.insert(
+            ACCESS_TOKENS_URL.to_string(),
+            format!(r#"{{"token":"{token}","expires_at":"2100-01-01T00:00:00Z"}}"#).into_bytes(),
+        );
+    }
+
+    #[test]
+    fn exchanges_a_jwt_for_an_installation_token() {
+        let host = FakeHost::default();
+        serve_token(&host, "ghs_new");
+
+        assert_eq!(
+            installation_token(&host, API_URL, &app()).unwrap(),
+            "ghs_new"
+        );
+
+        let state = host.state();
+        assert_eq!(state.requests, [format!("post {ACCESS_TOKENS_URL}")]);
+        let (name, authorization) = &state.request_headers[ACCESS_TOKENS_URL][0];
+        assert_eq!(name, "Authorization");
+        assert!(authorization.starts_with("Bearer eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9."));
+        drop(state);
+        let cached = cached(&host);
+        let entry = &cached[&cache_key(API_URL, &app())];
+        assert_eq!(entry.token, "ghs_new");
+        assert_eq!(entry.expires_at, 4_102_444_800);
+    }
+
+    #[test]
+    fn reuses_a_cached_token_until_it_is_about_to_expire() {
+        let host = FakeHost::default();
+        serve_token(&host, "ghs_new");
+        let key = cache_key(API_URL, &app());
+        let now = unix_time();
+        cache(
+            &host,
+            &[(&key, "ghs_cached", now + REFRESH_MARGIN_SECS + 60)],
+        );
+
+        assert_eq!(
+            installation_token(&host, API_URL, &app()).unwrap(),
+            "ghs_cached"
+        );
+        assert!(host.state().requests.is_empty());
+
+        cache(
+            &host,
+            &[(&key, "ghs_cached", now + REFRESH_MARGIN_SECS - 60)],
+        );
+
+        assert_eq!(
+            installation_token(&host, API_URL, &app()).unwrap(),
+            "ghs_new"
+        );
+        assert_eq!(host.state().requests.len(), 1);
+    }
+
+    #[test]
+    fn prunes_expired_tokens_of_other_installations() {
+        let host = FakeHost::default();
+        serve_token(&host, "ghs_new");
+        let now = unix_time();
+        cache(
+            &host,
+            &[
+                ("expired", "ghs_expired", now - 1),
+                ("valid", "ghs_valid", now + 3600),
+            ],
+        );
+
+        installation_token(&host, API_URL, &app()).unwrap();
+
+        let mut keys: Vec<_> = cached(&host).into_keys().collect();
+        keys.sort();
+        let mut expected = vec![cache_key(API_URL, &app()), "valid".to_string()];
+        expected.sort();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn reports_failed_token_exchanges() {
+        let host = FakeHost::default();
+        host.state().request_errors.insert(
+            ACCESS_TOKENS_URL.to_string(),
+            "status code 401 Unauthorized".to_string(),
+        );
+
+        let error = installation_token(&host, API_URL, &app()).unwrap_err();
+
+        assert!(
+            error.contains("failed to create a GitHub App installation token"),
+            "{error}"
+        );
+        assert!(error.contains("status code 401"), "{error}");
+        assert!(host.read_file(INSTALLATION_TOKENS_FILE).is_err());
+    }
+
+    #[test]
+    fn encodes_base64_url_without_padding() {
+        for (data, encoded) in [
+            (b"".as_slice(), ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg"),
+            (&[0xfb, 0xff, 0xbf], "-_-_"),
+        ] {
+            assert_eq!(base64_url_encode(data), encoded);
+        }
+    }
+
+    /// The JWT for app 12345 at 1700000000, where the signature is
+    /// `openssl dgst -sha256 -sign testdata/rsa_1024_pkcs1.pem` of the first two parts.
+    const JWT: &str = "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.\
+        eyJleHAiOjE3MDAwMDA1NDAsImlhdCI6MTY5OTk5OTk0MCwiaXNzIjoiMTIzNDUifQ.\
+        XLkVzrsOyCL6_fj_D824XFEnRcizSGjfVoOWObJH88iKWYX0Eqv7mqfSioDzp23waD1EGvDKQapJgby-OCyF\
+        rBLhzh0g7SuWYppxSHwtyAXIsWDbNNyiaAwegKy3BHqO75SFqq6IV6kKXLwJHKqFTJ73POGxtuNM5gnx2nX7VSE";
+
+    #[test]
+    fn signs_jwt_like_openssl() {
+        for pem in [
+            include_str!("testdata/rsa_1024_pkcs1.pem"),
+            include_str!("testdata/rsa_1024_pkcs8.pem"),
+        ] {
+            let key = private_key(pem).unwrap();
+
+            assert_eq!(jwt(12345, &key, 1_700_000_000).unwrap(), JWT);
+        }
+    }
+
+    #[test]
+    fn rejects_keys_that_cannot_sign() {
+        let pem = |label: &str, base64: &str| {
+            format!("-----BEGIN {label}-----\n{base64}\n-----END {label}-----\n")
+        };
+        for (pem, expected) in [
+            (
+                pem("ENCRYPTED PRIVATE KEY", "MIIC3TBXBgkqhkiG9w0BBQ0w"),
+                "encrypted private keys are not supported",
+            ),
+            ("ssh-rsa AAAAB3NzaC1yc2E".to_string(), ""),
+            (pem("RSA PRIVATE KEY", "MAA="), ""),
+            // An Ed25519 key.
+            (
+                pem(
+                    "PRIVATE KEY",
+                    "MC4CAQAwBQYDK2VwBCIEIJZjI6UMSUhakZpczSzubAZkw0q5IbH+UfYdQUaWG2G9",
+                ),
+                "",
+            ),
+        ] {
+            let error = private_key(&pem).unwrap_err();
+            assert!(error.contains(expected), "{pem}: {error}");
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! The parts of Zed's extension API and the filesystem that installing the server, the
+//! remote bridge, signing in, authenticating as a GitHub App and validating tokens rely on.
+//!
+//! Installation goes through [`Host`] so it can be exercised against [`fake::FakeHost`] in
+//! tests, without Zed or network access.
+
+use std::fs;
+use zed_extension_api::http_client::{HttpMethod, HttpRequest, HttpResponse, RedirectPolicy};
+use zed_extension_api::{self as zed, GithubRelease, Result};
+
+pub trait Host: Send + Sync {
+    fn current_platform(&self) -> (zed::Os, zed::Architecture);
+
+    /// Returns the latest non-prerelease release of `repo` that has assets.
+    fn latest_github_release(&self, repo: &str) -> Result<GithubRelease>;
+
+    fn github_release_by_tag_name(&self, repo: &str, tag: &str) -> Result<GithubRelease>;
+
+    /// Downloads `url` to `path` in the work directory, without decompressing it.
+    fn download_file(&self, url: &str, path: &str) -> Result<()>;
+
+    /// Returns the body of a `GET` request to `url`, following redirects.
+    fn fetch(&self, url: &str) -> Result<Vec<u8>>;
+
+    /// Returns the JSON body of a `POST` of the form-encoded `form` to `url`.
+    fn post_form(&self, url: &str, form: &str) -> Result<Vec<u8>>;
+
+    /// Sends a request without a body to the GitHub API at `url`, with `headers` added to
+    /// the ones every API request needs.
+    fn api_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<HttpResponse>;
+
+    fn make_file_executable(&self, path: &str) -> Result<()>;
+
+    fn is_file(&self, path: &str) -> bool;
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// Writes `contents` to `path`, creating its parent directories.
+    fn write_file(&self, path: &str, contents: &[u8]) -> Result<()>;
+
+    fn create_dir_all(&self, path: &str) -> Result<()>;
+
+    fn remove_file(&self, path: &str) -> Result<()>;
+
+    fn remove_dir_all(&self, path: &str) -> Result<()>;
+
+    /// Renames the file or directory `from` to `to`, which must not exist.
+    fn rename(&self, from: &str, to: &str) -> Result<()>;
+
+    /// Returns the names of the entries in the work directory.
+    fn read_work_dir(&self) -> Result<Vec<String>>;
+
+    fn npm_package_installed_version(&self, package: &str) -> Result<Option<String>>;
+
+    /// Installs `version` of `package` into the work directory's `node_modules`.
+    fn npm_install_package(&self, package: &str, version: &str) -> Result<()>;
+
+    /// Returns the path of the Node.js binary Zed bundles.
+    fn node_binary_path(&self) -> Result<String>;
+}
+
+/// The host Zed runs the extension in.
+pub struct ZedHost;
+
+impl Host for ZedHost {
+    fn current_platform(&self) -> (zed::Os, zed::Architecture) {
+        zed::current_platform()
+    }
+
+    fn latest_github_release(&self, repo: &str) -> Result<GithubRelease> {
+        zed::latest_github_release(
+            repo,
+            zed::GithubReleaseOptions {
+                require_assets: true,
+                pre_release: false,
+            },
+        )
+    }
+
+    fn github_release_by_tag_name(&self, repo: &str, tag: &str) -> Result<GithubRelease> {
+        zed::github_release_by_tag_name(repo, tag)
+    }
+
+    fn download_file(&self, url: &str, path: &str) -> Result<()> {
+        zed::download_file(url, path, zed::DownloadedFileType::Uncompressed)
+    }
+
+    fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let response = HttpRequest::builder()
+            .method(HttpMethod::Get)
+            .url(url)
+            .redirect_policy(RedirectPolicy::FollowAll)
+            .build()?
+            .fetch()?;
+        Ok(response.body)
+    }
+
+    fn post_form(&self, url: &str, form: &str) -> Result<Vec<u8>> {
+        let response = HttpRequest::builder()
+            .method(HttpMethod::Post)
+            .url(url)
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("User-Agent", "zed-mcp-server-github")
+            .body(form.as_bytes().to_vec())
+            .redirect_policy(RedirectPolicy::FollowLimit(3))
+            .build()?
+            .fetch()?;
+        Ok(response.body)
+    }
+
+    fn api_request(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<HttpResponse> {
+        headers
+            .iter()
+            .fold(HttpRequest::builder(), |request, (name, value)| {
+                request.header(*name, *value)
+            })
+            .method(method)
+            .url(url)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "zed-mcp-server-github")
+            .redirect_policy(RedirectPolicy::FollowLimit(3))
+            .build()?
+            .fetch()
+    }
+
+    fn make_file_executable(&self, path: &str) -> Result<()> {
+        zed::make_file_executable(path)
+    }
+
+    fn is_file(&self, path: &str) -> bool {
+        fs::metadata(path).is_ok_and(|stat| stat.is_file())
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>> {
+        fs::read(path).map_err(|e| e.to_string())
+    }
+
+    fn write_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<()> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())
+    }
+
+    fn remove_file(&self, path: &str) -> Result<()> {
+        fs::remove_file(path).map_err(|e| e.to_string())
+    }
+
+    fn remove_dir_all(&self, path: &str) -> Result<()> {
+        fs::remove_dir_all(path).map_err(|e| e.to_string())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<()> {
+        fs::rename(from, to).map_err(|e| e.to_string())
+    }
+
+    fn read_work_dir(&self) -> Result<Vec<String>> {
+        fs::read_dir(".")
+            .map_err(|e| e.to_string())?
+            .map(|entry| {
+                let entry = entry.map_err(|e| e.to_string())?;
+                Ok(entry.file_name().to_string_lossy().into_owned())
+            })
+            .collect()
+    }
+
+    fn npm_package_installed_version(&self, package: &str) -> Result<Option<String>> {
+        zed::npm_package_installed_version(package)
+    }
+
+    fn npm_install_package(&self, package: &str, version: &str) -> Result<()> {
+        zed::npm_install_package(package, version)
+    }
+
+    fn node_binary_path(&self) -> Result<String> {
+        zed::node_binary_path()
+    }
+}
+
+#[cfg(test)]
+pub mod fake {
+    //! An in-memory [`Host`] with canned releases and downloads.
+
+    use super::Host;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use zed_extension_api::http_client::{HttpMethod, HttpResponse};
+    use zed_extension_api::{self as zed, GithubRelease, Result};
+
+    #[derive(Default)]
+    pub struct State {
+        pub platform: Option<(zed::Os, zed::Architecture)>,
+        /// The latest release, or the error looking it up returns.
+        pub latest_release: Option<Result<GithubRelease>>,
+        pub releases_by_tag: HashMap<String, GithubRelease>,
+        /// The bodies served for URLs, by downloads, fetches and API requests.
+        pub responses: HashMap<String, Vec<u8>>,
+        /// The headers API requests to URLs receive along with their bodies.
+        pub response_headers: HashMap<String, Vec<(String, String)>>,
+        /// The errors API requests to URLs fail with, e.g. an unexpected status code.
+        pub request_errors: HashMap<String, String>,
+        /// The headers of the last API request to each URL.
+        pub request_headers: HashMap<String, Vec<(String, String)>>,
+        pub files: BTreeMap<String, Vec<u8>>,
+        pub dirs: BTreeSet<String>,
+        pub executables: BTreeSet<String>,
+        pub npm_installed_versions: HashMap<String, String>,
+        /// Every release lookup and request, in order.
+        pub requests: Vec<String>,
+    }
+
+    /// A [`Host`] whose state tests can inspect and change through any clone.
+    #[derive(Clone, Default)]
+    pub struct FakeHost(pub Arc<Mutex<State>>);
+
+    impl FakeHost {
+        pub fn state(&self) -> MutexGuard<'_, State> {
+            self.0.lock().unwrap()
+        }
+    }
+
+    impl Host for FakeHost {
+        fn current_platform(&self) -> (zed::Os, zed::Architecture) {
+            self.state()
+                .platform
+                .unwrap_or((zed::Os::Linux, zed::Architecture::X8664))
+        }
+
+        fn latest_github_release(&self, repo: &str) -> Result<GithubRelease> {
+            let mut state = self.state();
+            state.requests.push(format!("latest {repo}"));
+            state
+                .latest_release
+                .clone()
+                .unwrap_or_else(|| Err(format!("{repo} has no releases")))
+        }
+
+        fn github_release_by_tag_name(&self, repo: &str, tag: &str) -> Result<GithubRelease> {
+            let mut state = self.state();
+            state.requests.push(format!("tag {repo} {tag}"));
+            state
+                .releases_by_tag
+                .get(tag)
+                .cloned()
+                .ok_or_else(|| format!("{repo} has no release {tag}"))
+        }
+
+        fn download_file(&self, url: &str, path: &str) -> Result<()> {
+            let body = self.fetch(url)?;
+            self.write_file(path, &body)
+        }
+
+        fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+            let mut state = self.state();
+            state.requests.push(format!("get {url}"));
+            state
+                .responses
+                .get(url)
+                .cloned()
+                .ok_or_else(|| format!("404 Not Found: {url}"))
+        }
+
+        fn post_form(&self, url: &str, form: &str) -> Result<Vec<u8>> {
+            let mut state = self.state();
+            state.requests.push(format!("post {url} {form}"));
+            state
+                .responses
+                .get(url)
+                .cloned()
+                .ok_or_else(|| format!("404 Not Found: {url}"))
+        }
+
+        fn api_request(
+            &self,
+            method: HttpMethod,
+            url: &str,
+            headers: &[(&str, &str)],
+        ) -> Result<HttpResponse> {
+            let mut state = self.state();
+            let method = match method {
+                HttpMethod::Get => "get",
+                HttpMethod::Post => "post",
+                _ => "other",
+            };
+            state.requests.push(format!("{method} {url}"));
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            state.request_headers.insert(url.to_string(), headers);
+            if let Some(error) = state.request_errors.get(url) {
+                return Err(error.clone());
+            }
+            let body = state
+                .responses
+                .get(url)
+                .cloned()
+                .ok_or_else(|| format!("404 Not Found: {url}"))?;
+            Ok(HttpResponse {
+                headers: state.response_headers.get(url).cloned().unwrap_or_default(),
+                body,
+            })
+        }
+
+        fn make_file_executable(&self, path: &str) -> Result<()> {
+            if !self.is_file(path) {
+                return Err(format!("no such file: {path}"));
+            }
+            self.state().executables.insert(path.to_string());
+            Ok(())
+        }
+
+        fn is_file(&self, path: &str) -> bool {
+            self.state().files.contains_key(path)
+        }
+
+        fn read_file(&self, path: &str) -> Result<Vec<u8>> {
+            self.state()
+                .files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| format!("no such file: {path}"))
+        }
+
+        fn write_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                self.create_dir_all(parent)?;
+            }
+            self.state()
+                .files
+                .insert(path.to_string(), contents.to_vec());
+            Ok(())
+        }
+
+        fn create_dir_all(&self, path: &str) -> Result<()> {
+            let mut state = self.state();
+            let mut dir = String::new();
+            for component in path.split('/') {
+                if !dir.is_empty() {
+                    dir.push('/');
+                }
+                dir.push_str(component);
+                state.dirs.insert(dir.clone());
+            }
+            Ok(())
+        }
+
+        fn remove_file(&self, path: &str) -> Result<()> {
+            self.state()
+                .files
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| format!("no such file: {path}"))
+        }
+
+        fn remove_dir_all(&self, path: &str) -> Result<()> {
+            let mut state = self.state();
+            let prefix = format!("{path}/");
+            state.files.retain(|file, _| !file.starts_with(&prefix));
+            state
+                .dirs
+                .retain(|dir| dir != path && !dir.starts_with(&prefix));
+            Ok(())
+        }
+
+        fn rename(&self, from: &str, to: &str) -> Result<()> {
+            let mut state = self.state();
+            if state.files.contains_key(to) || state.dirs.contains(to) {
+                return Err(format!("'{to}' already exists"));
+            }
+            let rename = |path: &str| -> Option<String> {
+                let rest = path.strip_prefix(from)?;
+                (rest.is_empty() || rest.starts_with('/')).then(|| format!("{to}{rest}"))
+            };
+            state.files = std::mem::take(&mut state.files)
+                .into_iter()
+                .map(|(path, contents)| (rename(&path).unwrap_or(path), contents))
+                .collect();
+            state.dirs = std::mem::take(&mut state.dirs)
+                .into_iter()
+                .map(|dir| rename(&dir).unwrap_or(dir))
+                .collect();
+            state.executables = std::mem::take(&mut state.executables)
+                .into_iter()
+                .map(|path| rename(&path).unwrap_or(path))
+                .collect();
+            Ok(())
+        }
+
+        fn read_work_dir(&self) -> Result<Vec<String>> {
+            let state = self.state();
+            let names: BTreeSet<_> = state
+                .files
+                .keys()
+                .chain(&state.dirs)
+                .map(|path| path.split('/').next().unwrap_or(path).to_string())
+                .collect();
+            Ok(names.into_iter().collect())
+        }
+
+        fn npm_package_installed_version(&self, package: &str) -> Result<Option<String>> {
+            Ok(self.state().npm_installed_versions.get(package).cloned())
+        }
+
+        fn npm_install_package(&self, package: &str, version: &str) -> Result<()> {
+            let mut state = self.state();
+            state
+                .requests
+                .push(format!("npm install {package}@{version}"));
+            state
+                .npm_installed_versions
+                .insert(package.to_string(), version.to_string());
+            Ok(())
+        }
+
+        fn node_binary_path(&self) -> Result<String> {
+            Ok("/zed/node/bin/node".to_string())
+        }
+    }
+}
@@ -1,109 +1,670 @@
-use schemars::JsonSchema;
-use serde::Deserialize;
-use std::fs;
+mod archive;
+mod asset;
+mod checksum;
+mod error;
+mod github_app;
+mod host;
+mod oauth;
+mod preflight;
+mod remote;
+mod repository;
+mod settings;
+mod token;
+mod trust;
+
+use asset::AssetKind;
+use error::Error;
+use host::{Host, ZedHost};
+use oauth::{LoginSettings, LoginStatus};
+use repository::Repository;
+use semver::Version;
+use settings::{GitHubContextServerSettings, Mode, Runtime};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use zed::settings::ContextServerSettings;
 use zed_extension_api::{
     self as zed, serde_json, Command, ContextServerConfiguration, ContextServerId, Project, Result,
+    SlashCommand, SlashCommandOutput, SlashCommandOutputSection, Worktree,
 };
 
+const CONTEXT_SERVER_ID: &str = "mcp-server-github";
+const READ_ONLY_CONTEXT_SERVER_ID: &str = "mcp-server-github-readonly";
 const REPO_NAME: &str = "github/github-mcp-server";
 const BINARY_NAME: &str = "github-mcp-server";
+const TOKEN_ENV_VAR: &str = "GITHUB_PERSONAL_ACCESS_TOKEN";
+const DEFAULT_TOKEN_EXPIRY_WARNING_DAYS: u32 = 7;
+/// Releases are unpacked into `staging-github-mcp-server-<version>` and only renamed to
+/// `github-mcp-server-<version>` once complete, so a version directory is never partial.
+const STAGING_DIR_PREFIX: &str = "staging-";
+/// Written last into a staged install, so installs predating staging can be told apart.
+const INSTALLED_MARKER: &str = ".installed";
+const MISSING_TOKEN_ERROR: &str = "no GitHub token configured: set one of \
+    `github_personal_access_token`, `token_env`, `token_file` or `token_command`, or set \
+    `oauth_client_id` to sign in with /github-login, or `app_id`, `installation_id` and \
+    `private_key_path` to authenticate as a GitHub App";
 
-#[derive(Debug, Deserialize, JsonSchema)]
-struct GitHubContextServerSettings {
-    github_personal_access_token: String,
+/// The settings of a project's server that `/github-repo` needs.
+#[derive(Clone)]
+struct ProjectRepository {
+    /// The host name of the GitHub instance the server was last started for.
+    hostname: String,
+    /// The `default_repository` the server was last started with.
+    default_repository: Option<Repository>,
 }
 
 struct GitHubModelContextExtension {
+    host: Box<dyn Host>,
     cached_binary_path: Option<String>,
+    /// The version directories of the binaries handed out so far. One extension instance
+    /// serves every open project, and projects may pin different versions, so these are
+    /// never pruned.
+    versions_in_use: HashSet<String>,
+    /// Whether staging directories left by interrupted installs have been removed yet.
+    swept_staging_dirs: bool,
+    /// What slash commands need to know about each project's server, by the ids of the
+    /// project's worktrees, since one extension instance serves every open project.
+    project_repositories: HashMap<u64, ProjectRepository>,
+    /// A warning about the token expiring soon, surfaced in the installation instructions.
+    token_warning: Option<String>,
+    /// The OAuth app `/github-login` signs in with, from the last server start.
+    login: Option<LoginSettings>,
+    /// The tokens resolved so far, redacted from errors and warnings even when they don't look
+    /// like GitHub tokens.
+    secrets: Vec<String>,
 }
 
 impl GitHubModelContextExtension {
-    fn context_server_binary_path(
-        &mut self,
-        _context_server_id: &ContextServerId,
-    ) -> Result<String> {
-        if let Some(path) = &self.cached_binary_path {
-            if fs::metadata(path).map_or(false, |stat| stat.is_file()) {
-                return Ok(path.clone());
-            }
+    fn context_server_binary_path(&mut self, pinned_tag: Option<&str>) -> Result<String, Error> {
+        let host = self.host.as_ref();
+        let (platform, arch) = host.current_platform();
+
+        if !self.swept_staging_dirs {
+            sweep_staging_dirs(host);
+            self.swept_staging_dirs = true;
         }
 
-        let release = zed::latest_github_release(
-            REPO_NAME,
-            zed::GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )?;
-
-        let (platform, arch) = zed::current_platform();
-        let asset_name = format!(
-            "{BINARY_NAME}_{os}_{arch}.{ext}",
-            arch = match arch {
-                zed::Architecture::Aarch64 => "arm64",
-                zed::Architecture::X86 => "i386",
-                zed::Architecture::X8664 => "x86_64",
-            },
-            os = match platform {
-                zed::Os::Mac => "Darwin",
-                zed::Os::Linux => "Linux",
-                zed::Os::Windows => "Windows",
-            },
-            ext = match platform {
-                zed::Os::Mac | zed::Os::Linux => "tar.gz",
-                zed::Os::Windows => "zip",
+        let release = match pinned_tag {
+            Some(tag) => {
+                // Pinned versions are immutable, so an existing install can be reused without
+                // asking GitHub about the release again.
+                let version_dir = version_dir(tag);
+                if is_installed(host, &version_dir) {
+                    let binary_path = binary_path(&version_dir, platform);
+                    self.versions_in_use.insert(version_dir);
+                    return Ok(binary_path);
+                }
+
+                host.github_release_by_tag_name(REPO_NAME, tag)
+                    .map_err(|e| {
+                        Error::ReleaseLookup(format!(
+                            "failed to find release '{tag}' of {REPO_NAME}: {e}"
+                        ))
+                    })?
             }
-        );
+            None => {
+                if let Some(path) = &self.cached_binary_path {
+                    if host.is_file(path) {
+                        return Ok(path.clone());
+                    }
+                }
 
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == asset_name)
-            .ok_or_else(|| format!("no asset found matching {:?}", asset_name))?;
-
-        let version_dir = format!("{BINARY_NAME}-{}", release.version);
-        fs::create_dir_all(&version_dir)
-            .map_err(|err| format!("failed to create directory '{version_dir}': {err}"))?;
-        let binary_path = format!(
-            "{version_dir}/{BINARY_NAME}{suffix}",
-            suffix = match platform {
-                zed::Os::Windows => ".exe",
-                _ => "",
+                match host.latest_github_release(REPO_NAME) {
+                    Ok(release) => release,
+                    Err(err) => {
+                        // The fallback isn't cached so the next start tries GitHub again.
+                        let Some(path) = newest_installed_binary(host, platform) else {
+                            return Err(Error::ReleaseLookup(format!(
+                                "failed to look up the latest {REPO_NAME} release: {err}"
+                            )));
+                        };
+                        error::warn(
+                            &format!(
+                                "failed to look up the latest {REPO_NAME} release ({err}), \
+                                 falling back to installed '{path}'"
+                            ),
+                            &self.secrets,
+                        );
+                        if let Some((version_dir, _)) = path.split_once('/') {
+                            self.versions_in_use.insert(version_dir.to_string());
+                        }
+                        return Ok(path);
+                    }
+                }
             }
-        );
+        };
 
-        if !fs::metadata(&binary_path).map_or(false, |stat| stat.is_file()) {
-            let file_kind = match platform {
-                zed::Os::Mac | zed::Os::Linux => zed::DownloadedFileType::GzipTar,
-                zed::Os::Windows => zed::DownloadedFileType::Zip,
-            };
+        let (asset, asset_kind) = asset::select(&release.assets, platform, arch).map_err(|e| {
+            Error::AssetNotFound(format!(
+                "release {} of {REPO_NAME} has {e}",
+                release.version
+            ))
+        })?;
 
-            zed::download_file(&asset.download_url, &version_dir, file_kind)
-                .map_err(|e| format!("failed to download file: {e}"))?;
+        let version_dir_prefix = version_dir("");
+        let version_dir = version_dir(&release.version);
+        let binary_path = binary_path(&version_dir, platform);
+        self.versions_in_use.insert(version_dir.clone());
 
-            zed::make_file_executable(&binary_path)?;
+        if !is_installed(host, &version_dir) {
+            let staging_dir = format!("{STAGING_DIR_PREFIX}{version_dir}");
+            let staged = install(host, &release, asset, asset_kind, &staging_dir, platform)
+                .and_then(|()| {
+                    // A directory without the marker is left over from an interrupted install
+                    // by an older version of the extension.
+                    host.remove_dir_all(&version_dir).ok();
+                    host.rename(&staging_dir, &version_dir).map_err(|e| {
+                        Error::Filesystem(format!(
+                            "failed to move '{staging_dir}' to '{version_dir}': {e}"
+                        ))
+                    })
+                });
+            if let Err(error) = staged {
+                host.remove_dir_all(&staging_dir).ok();
+                return Err(error);
+            }
 
-            // Removes old versions
-            let entries =
-                fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("failed to load directory entry {e}"))?;
-                if entry.file_name().to_str() != Some(&version_dir) {
-                    fs::remove_dir_all(entry.path()).ok();
+            // Removes versions left by earlier sessions, leaving anything else in the work dir
+            // (such as the remote bridge's node_modules) alone.
+            let entries = host
+                .read_work_dir()
+                .map_err(|e| Error::Filesystem(format!("failed to list working directory: {e}")))?;
+            for file_name in entries {
+                if file_name.starts_with(&version_dir_prefix)
+                    && !self.versions_in_use.contains(&file_name)
+                {
+                    host.remove_dir_all(&file_name).ok();
                 }
             }
         }
 
-        self.cached_binary_path = Some(binary_path.clone());
+        if pinned_tag.is_none() {
+            self.cached_binary_path = Some(binary_path.clone());
+        }
         Ok(binary_path)
     }
+
+    /// Resolves the token of the user the server acts as, from the configured token source or
+    /// a `/github-login` sign-in.
+    fn user_token(
+        &self,
+        settings: &GitHubContextServerSettings,
+        profile: Option<String>,
+    ) -> Result<String, Error> {
+        let signed_in_token = match (settings.token.source(), &self.login) {
+            (None, Some(login)) => oauth::token(self.host.as_ref(), login).map_err(Error::Token)?,
+            _ => None,
+        };
+        match (settings.token.source(), signed_in_token, profile) {
+            (Some(source), _, _) => source.resolve().map_err(Error::Token),
+            (None, Some(token), _) => Ok(token),
+            (None, None, _) if self.login.is_some() => Err(Error::Token(
+                "not signed in to GitHub yet, run /github-login in the agent panel".into(),
+            )),
+            (None, None, Some(profile)) => Err(Error::Token(format!(
+                "profile '{profile}': {MISSING_TOKEN_ERROR}"
+            ))),
+            (None, None, None) => Err(Error::Token(MISSING_TOKEN_ERROR.into())),
+        }
+    }
+
+    fn server_command(
+        &mut self,
+        context_server_id: &ContextServerId,
+        project: &Project,
+    ) -> Result<Command, Error> {
+        let read_only_server = match context_server_id.as_ref() {
+            CONTEXT_SERVER_ID => false,
+            READ_ONLY_CONTEXT_SERVER_ID => true,
+            id => return Err(Error::Settings(format!("unknown context server: \"{id}\""))),
+        };
+
+        // The read-only server falls back to the full-access server's settings, so a token
+        // only has to be configured once.
+        let mut settings = ContextServerSettings::for_project(context_server_id.as_ref(), project)
+            .map_err(Error::Settings)?;
+        if settings.settings.is_none() && read_only_server {
+            settings = ContextServerSettings::for_project(CONTEXT_SERVER_ID, project)
+                .map_err(Error::Settings)?;
+        }
+        let Some(raw_settings) = settings.settings else {
+            return Err(Error::Token(MISSING_TOKEN_ERROR.into()));
+        };
+        let mut settings: GitHubContextServerSettings =
+            serde_json::from_value(raw_settings.clone())
+                .map_err(|e| Error::Settings(format!("invalid GitHub MCP server settings: {e}")))?;
+        let profile = settings.apply_profile().map_err(Error::Settings)?;
+        trust::ensure_trusted(self.host.as_ref(), &raw_settings, profile.as_deref())
+            .map_err(Error::Settings)?;
+        if read_only_server {
+            settings.read_only = true;
+        }
+        settings.validate_toolsets().map_err(Error::Settings)?;
+        settings.validate_passthrough().map_err(Error::Settings)?;
+        let api_url = settings.api_url().map_err(Error::Settings)?;
+        // Slash commands can't read context server settings, so remember what they need.
+        let project_repository = ProjectRepository {
+            hostname: settings.github_hostname().map_err(Error::Settings)?,
+            default_repository: settings.default_repository().map_err(Error::Settings)?,
+        };
+        for worktree_id in project.worktree_ids() {
+            self.project_repositories
+                .insert(worktree_id, project_repository.clone());
+        }
+        self.login = match &settings.oauth_client_id {
+            Some(client_id) => Some(LoginSettings {
+                web_url: settings.web_url().map_err(Error::Settings)?,
+                client_id: client_id.clone(),
+                scopes: preflight::required_scopes(&settings.toolsets, settings.read_only)
+                    .join(" "),
+            }),
+            None => None,
+        };
+
+        let github_app = settings.github_app().map_err(Error::Settings)?;
+        let token = match &github_app {
+            Some(app) => github_app::installation_token(self.host.as_ref(), &api_url, app)
+                .map_err(Error::Token)?,
+            None => self.user_token(&settings, profile)?,
+        };
+        if !self.secrets.contains(&token) {
+            self.secrets.push(token.clone());
+        }
+        // Installation tokens can't call `GET /user`, have no OAuth scopes, and are replaced
+        // before they expire, so only user tokens are checked.
+        self.token_warning = None;
+        if github_app.is_none() {
+            let expiration = preflight::validate_token(
+                self.host.as_ref(),
+                &api_url,
+                &token,
+                // Without a token source, the token comes from `/github-login`.
+                settings.token.source().is_none(),
+                &settings.toolsets,
+                settings.read_only,
+            )
+            .map_err(Error::Token)?;
+            self.token_warning = match expiration {
+                Some(expiration) => preflight::check_expiration(
+                    &expiration,
+                    settings
+                        .token_expiry_warning_days
+                        .unwrap_or(DEFAULT_TOKEN_EXPIRY_WARNING_DAYS),
+                    &settings.token_settings_url().map_err(Error::Settings)?,
+                    unix_time(),
+                )
+                .map_err(Error::Token)?,
+                None => None,
+            };
+        }
+        if let Some(warning) = &self.token_warning {
+            error::warn(warning, &self.secrets);
+        }
+
+        let mut extra_env: Vec<_> = settings
+            .env
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        extra_env.sort();
+
+        if settings.mode == Mode::Remote {
+            return remote::bridge_command(
+                self.host.as_ref(),
+                &settings.remote_url().map_err(Error::Settings)?,
+                token,
+                &settings.remote_headers(),
+                extra_env,
+            )
+            .map_err(Error::Download);
+        }
+
+        let mut server_args = vec!["stdio".to_string()];
+        if settings.read_only {
+            server_args.push("--read-only".to_string());
+        }
+        if settings.dynamic_toolsets {
+            server_args.push("--dynamic-toolsets".to_string());
+        }
+        server_args.extend(settings.extra_args.iter().cloned());
+
+        let mut env = vec![(TOKEN_ENV_VAR.into(), token)];
+        if let Some(host) = settings.github_host().map_err(Error::Settings)? {
+            env.push(("GITHUB_HOST".into(), host));
+        }
+        if let Some(toolsets) = settings.toolsets_env() {
+            env.push(("GITHUB_TOOLSETS".into(), toolsets));
+        }
+        env.extend(settings.tool_description_env().map_err(Error::Settings)?);
+        extra_env.retain(|(name, _)| !env.iter().any(|(managed, _)| managed == name));
+        env.extend(extra_env);
+
+        let (command, args) = match (settings.runtime, &settings.binary) {
+            (Runtime::Docker, _) => {
+                // Only variable names go on the command line; the container CLI reads the
+                // values, including the token, from its own environment.
+                let mut args = vec!["run".to_string(), "-i".to_string(), "--rm".to_string()];
+                for (name, _) in &env {
+                    args.extend(["-e".to_string(), name.clone()]);
+                }
+                args.push(settings.container_image().map_err(Error::Settings)?);
+                args.extend(server_args);
+                (settings.container_cli.command().to_string(), args)
+            }
+            (Runtime::Binary, Some(binary)) => {
+                binary.validate().map_err(Error::Platform)?;
+                // Custom arguments replace the subcommand, but the settings-driven flags
+                // still apply.
+                let args = match &binary.arguments {
+                    Some(arguments) => arguments
+                        .iter()
+                        .cloned()
+                        .chain(server_args.into_iter().skip(1))
+                        .collect(),
+                    None => server_args,
+                };
+                (binary.path.clone(), args)
+            }
+            (Runtime::Binary, None) => {
+                let command =
+                    self.context_server_binary_path(settings.pinned_release_tag().as_deref())?;
+                (command, server_args)
+            }
+        };
+
+        Ok(Command { command, args, env })
+    }
+
+    fn slash_command(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&Worktree>,
+    ) -> Result<SlashCommandOutput, Error> {
+        match command.name.as_str() {
+            "github-login" => {
+                let login = self.login.as_ref().ok_or_else(|| {
+                    Error::Settings(
+                        "set `oauth_client_id` in the GitHub MCP server settings and start the \
+                         server before running /github-login"
+                            .into(),
+                    )
+                })?;
+                let text = match oauth::login(self.host.as_ref(), login).map_err(Error::Token)? {
+                    LoginStatus::SignedIn => format!(
+                        "Signed in to {}. Restart the GitHub MCP server to use the new token.",
+                        login.web_url
+                    ),
+                    LoginStatus::Pending {
+                        user_code,
+                        verification_uri,
+                    } => format!(
+                        "Open {verification_uri} and enter the code {user_code}. Then run \
+                         /github-login again, or restart the GitHub MCP server."
+                    ),
+                };
+                Ok(slash_command_output(text, "GitHub sign-in".to_string()))
+            }
+            "github-trust-settings" => {
+                let text = trust::review_pending(
+                    self.host.as_ref(),
+                    &self.secrets,
+                    args.first().map(String::as_str),
+                )
+                .map_err(Error::Settings)?;
+                Ok(slash_command_output(
+                    text,
+                    "GitHub MCP server settings".to_string(),
+                ))
+            }
+            "github-repo" => {
+                let worktree = worktree.ok_or_else(|| {
+                    Error::Settings("/github-repo must be run in a project".into())
+                })?;
+                let project_repository = self.project_repositories.get(&worktree.id());
+                let hostname =
+                    project_repository.map_or("github.com", |project| project.hostname.as_str());
+                let repository = match project_repository
+                    .and_then(|project| project.default_repository.as_ref())
+                {
+                    Some(repository) => repository.clone(),
+                    None => {
+                        let config = worktree.read_text_file(".git/config").map_err(|e| {
+                            Error::Filesystem(format!(
+                                "failed to read .git/config of {}: {e}",
+                                worktree.root_path()
+                            ))
+                        })?;
+                        Repository::from_git_config(&config, hostname).ok_or_else(|| {
+                            Error::Settings(format!(
+                                "no {hostname} remote found in {}/.git/config, \
+                                 set `default_repository` instead",
+                                worktree.root_path()
+                            ))
+                        })?
+                    }
+                };
+
+                let text = format!(
+                    "This project is the GitHub repository {repository} on {host}. \
+                     Use owner `{owner}` and repo `{name}` for GitHub tools unless told otherwise.",
+                    host = repository.host,
+                    owner = repository.owner,
+                    name = repository.name,
+                );
+                Ok(slash_command_output(
+                    text,
+                    format!("GitHub repository {repository}"),
+                ))
+            }
+            command => Err(Error::Settings(format!(
+                "unknown slash command: \"{command}\""
+            ))),
+        }
+    }
+}
+
+/// Downloads, verifies and unpacks `asset` into `staging_dir`, and marks the result as
+/// complete once the binary has been checked.
+fn install(
+    host: &dyn Host,
+    release: &zed::GithubRelease,
+    asset: &zed::GithubReleaseAsset,
+    asset_kind: AssetKind,
+    staging_dir: &str,
+    platform: zed::Os,
+) -> Result<(), Error> {
+    host.remove_dir_all(staging_dir).ok();
+    host.create_dir_all(staging_dir).map_err(|err| {
+        Error::Filesystem(format!("failed to create directory '{staging_dir}': {err}"))
+    })?;
+    let expected_digest = release_digest(host, release, &asset.name)?;
+
+    // Download the asset as-is so the bytes we verify are the bytes we install.
+    let download_path = format!("{staging_dir}/{}", asset.name);
+    host.download_file(&asset.download_url, &download_path)
+        .map_err(|e| Error::Download(format!("failed to download {}: {e}", asset.name)))?;
+    let download = host.read_file(&download_path).map_err(|e| {
+        Error::Filesystem(format!(
+            "failed to read downloaded file '{download_path}': {e}"
+        ))
+    })?;
+    host.remove_file(&download_path).ok();
+
+    let actual_digest = checksum::sha256_hex(&download);
+    if actual_digest != expected_digest {
+        return Err(Error::Download(format!(
+            "checksum mismatch for {}: expected sha256 {expected_digest}, got {actual_digest}",
+            asset.name
+        )));
+    }
+
+    let files = match asset_kind {
+        AssetKind::Binary => Vec::new(),
+        AssetKind::Archive(archive_kind) => archive::files(&download, archive_kind)
+            .map_err(|e| Error::Download(format!("failed to extract {}: {e}", asset.name)))?,
+    };
+    let binary = match asset_kind {
+        AssetKind::Binary => download,
+        AssetKind::Archive(_) => archive_binary(&files, platform)
+            .ok_or_else(|| {
+                Error::Download(format!("{} does not contain {BINARY_NAME}", asset.name))
+            })?
+            .to_vec(),
+    };
+    for (relative_path, contents) in &files {
+        let path = Path::new(staging_dir).join(relative_path);
+        let path = path.to_string_lossy();
+        host.write_file(&path, contents)
+            .map_err(|e| Error::Filesystem(format!("failed to write '{path}': {e}")))?;
+    }
+    // Raw binaries have arbitrary names and archives may nest the binary in a directory, so
+    // it is always (re)written to the path the server is run from.
+    let binary_path = binary_path(staging_dir, platform);
+    host.write_file(&binary_path, &binary)
+        .map_err(|e| Error::Filesystem(format!("failed to write '{binary_path}': {e}")))?;
+    host.make_file_executable(&binary_path).map_err(|e| {
+        Error::Filesystem(format!("failed to make '{binary_path}' executable: {e}"))
+    })?;
+
+    let written = host
+        .read_file(&binary_path)
+        .map_err(|e| Error::Filesystem(format!("failed to read back '{binary_path}': {e}")))?;
+    if binary.is_empty() || written != binary {
+        return Err(Error::Filesystem(format!(
+            "'{binary_path}' is empty or was not written completely"
+        )));
+    }
+
+    let marker_path = format!("{staging_dir}/{INSTALLED_MARKER}");
+    host.write_file(&marker_path, release.version.as_bytes())
+        .map_err(|e| Error::Filesystem(format!("failed to write '{marker_path}': {e}")))
+}
+
+/// Whether `version_dir` holds a complete install, as opposed to one that was interrupted
+/// before staging was introduced.
+fn is_installed(host: &dyn Host, version_dir: &str) -> bool {
+    host.is_file(&format!("{version_dir}/{INSTALLED_MARKER}"))
+}
+
+/// Removes staging directories left behind by installs that were interrupted.
+fn sweep_staging_dirs(host: &dyn Host) {
+    let Ok(entries) = host.read_work_dir() else {
+        return;
+    };
+    for file_name in entries {
+        if file_name.starts_with(STAGING_DIR_PREFIX) {
+            host.remove_dir_all(&file_name).ok();
+        }
+    }
+}
+
+/// Returns the published SHA-256 digest of `asset_name` from the release's checksums asset.
+fn release_digest(
+    host: &dyn Host,
+    release: &zed::GithubRelease,
+    asset_name: &str,
+) -> Result<String, Error> {
+    let checksums_asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name.ends_with("checksums.txt"))
+        .ok_or_else(|| {
+            Error::AssetNotFound(format!(
+                "release {} has no checksums asset",
+                release.version
+            ))
+        })?;
+
+    let checksums = host.fetch(&checksums_asset.download_url).map_err(|e| {
+        Error::Download(format!("failed to download {}: {e}", checksums_asset.name))
+    })?;
+    let checksums = String::from_utf8(checksums).map_err(|e| {
+        Error::Download(format!("{} is not valid UTF-8: {e}", checksums_asset.name))
+    })?;
+
+    checksum::expected_digest(&checksums, asset_name).ok_or_else(|| {
+        Error::Download(format!(
+            "{} has no entry for {asset_name}",
+            checksums_asset.name
+        ))
+    })
+}
+
+/// Returns the contents of the server binary in an archive, preferring the least nested one.
+fn archive_binary(files: &[(PathBuf, Vec<u8>)], platform: zed::Os) -> Option<&[u8]> {
+    let file_name = binary_file_name(platform);
+    files
+        .iter()
+        .filter(|(path, _)| {
+            path.file_name()
+                .is_some_and(|name| name == file_name.as_str())
+        })
+        .min_by_key(|(path, _)| path.components().count())
+        .map(|(_, contents)| contents.as_slice())
+}
+
+/// Returns the binary of the highest installed version, if any is usable.
+fn newest_installed_binary(host: &dyn Host, platform: zed::Os) -> Option<String> {
+    let prefix = version_dir("");
+    host.read_work_dir()
+        .ok()?
+        .into_iter()
+        .filter_map(|dir_name| {
+            let version = dir_name.strip_prefix(&prefix)?;
+            let version = Version::parse(version.trim_start_matches('v')).ok()?;
+            is_installed(host, &dir_name).then(|| (version, binary_path(&dir_name, platform)))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, binary_path)| binary_path)
+}
+
+/// Returns the current Unix time in seconds.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+fn slash_command_output(text: String, label: String) -> SlashCommandOutput {
+    SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label,
+        }],
+        text,
+    }
+}
+
+fn version_dir(version: &str) -> String {
+    format!("{BINARY_NAME}-{version}")
+}
+
+fn binary_path(version_dir: &str, platform: zed::Os) -> String {
+    format!("{version_dir}/{}", binary_file_name(platform))
+}
+
+fn binary_file_name(platform: zed::Os) -> String {
+    format!(
+        "{BINARY_NAME}{suffix}",
+        suffix = match platform {
+            zed::Os::Windows => ".exe",
+            _ => "",
+        }
+    )
 }
 
 impl zed::Extension for GitHubModelContextExtension {
     fn new() -> Self {
         Self {
+            host: Box::new(ZedHost),
             cached_binary_path: None,
+            versions_in_use: HashSet::new(),
+            swept_staging_dirs: false,
+            project_repositories: HashMap::new(),
+            token_warning: None,
+            login: None,
+            secrets: Vec::new(),
         }
     }
 
@@ -112,30 +673,39 @@ impl zed::Extension for GitHubModelContextExtension {
         context_server_id: &ContextServerId,
         project: &Project,
     ) -> Result<Command> {
-        let settings = ContextServerSettings::for_project("mcp-server-github", project)?;
-        let Some(settings) = settings.settings else {
-            return Err("missing `github_personal_access_token` setting".into());
-        };
-        let settings: GitHubContextServerSettings =
-            serde_json::from_value(settings).map_err(|e| e.to_string())?;
-
-        Ok(Command {
-            command: self.context_server_binary_path(context_server_id)?,
-            args: vec!["stdio".to_string()],
-            env: vec![(
-                "GITHUB_PERSONAL_ACCESS_TOKEN".into(),
-                settings.github_personal_access_token,
-            )],
-        })
+        self.server_command(context_server_id, project)
+            .map_err(|e| e.to_string_redacting(&self.secrets))
+    }
+
+    fn run_slash_command(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&Worktree>,
+    ) -> Result<SlashCommandOutput, String> {
+        self.slash_command(command, args, worktree)
+            .map_err(|e| e.to_string_redacting(&self.secrets))
     }
 
     fn context_server_configuration(
         &mut self,
-        _context_server_id: &ContextServerId,
+        context_server_id: &ContextServerId,
         _project: &Project,
     ) -> Result<Option<ContextServerConfiguration>> {
-        let installation_instructions =
+        let mut installation_instructions =
             include_str!("../configuration/installation_instructions.md").to_string();
+        if context_server_id.as_ref() == READ_ONLY_CONTEXT_SERVER_ID {
+            installation_instructions.insert_str(
+                0,
+                &format!(
+                    "This server always runs in read-only mode, whatever `read_only` is set to. \
+                     If it has no settings of its own, it uses the settings of `{CONTEXT_SERVER_ID}`.\n\n"
+                ),
+            );
+        }
+        if let Some(warning) = &self.token_warning {
+            installation_instructions.insert_str(0, &format!("**Warning:** {warning}\n\n"));
+        }
         let default_settings = include_str!("../configuration/default_settings.jsonc").to_string();
         let settings_schema =
             serde_json::to_string(&schemars::schema_for!(GitHubContextServerSettings))
@@ -150,3 +720,469 @@ impl zed::Extension for GitHubModelContextExtension {
 }
 
 zed::register_extension!(GitHubModelContextExtension);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use host::fake::FakeHost;
+    use zed::{GithubRelease, GithubReleaseAsset};
+
+    const LINUX_ASSET: &str = "github-mcp-server_Linux_x86_64.tar.gz";
+    const MAC_ASSET: &str = "github-mcp-server_Darwin_arm64.tar.gz";
+
+    fn extension(host: &FakeHost) -> GitHubModelContextExtension {
+        GitHubModelContextExtension {
+            host: Box::new(host.clone()),
+            ..zed::Extension::new()
+        }
+    }
+
+    fn tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            tar.append_data(&mut header, name, *contents).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// Serves a release with the given archives and a checksums file covering them.
+    fn publish(host: &FakeHost, version: &str, asset_names: &[&str]) -> GithubRelease {
+        let archive = tar_gz(&[
+            (BINARY_NAME, b"#!/bin/sh\n"),
+            ("README.md", b"# github-mcp-server\n"),
+        ]);
+        let mut state = host.state();
+        let mut assets = Vec::new();
+        let mut checksums = String::new();
+        for name in asset_names {
+            let download_url = format!("https://example.com/{version}/{name}");
+            state
+                .responses
+                .insert(download_url.clone(), archive.clone());
+            checksums.push_str(&format!("{}  {name}\n", checksum::sha256_hex(&archive)));
+            assets.push(GithubReleaseAsset {
+                name: name.to_string(),
+                download_url,
+            });
+        }
+        let checksums_url = format!("https://example.com/{version}/checksums.txt");
+        state
+            .responses
+            .insert(checksums_url.clone(), checksums.into_bytes());
+        assets.push(GithubReleaseAsset {
+            name: format!("{BINARY_NAME}_{version}_checksums.txt"),
+            download_url: checksums_url,
+        });
+
+        GithubRelease {
+            version: version.to_string(),
+            assets,
+        }
+    }
+
+    fn publish_latest(host: &FakeHost, version: &str, asset_names: &[&str]) {
+        let release = publish(host, version, asset_names);
+        host.state().latest_release = Some(Ok(release));
+    }
+
+    /// Records a complete install of `version`, as left by an earlier start.
+    fn mark_installed(host: &FakeHost, version: &str) {
+        let mut state = host.state();
+        let version_dir = format!("{BINARY_NAME}-{version}");
+        state
+            .files
+            .insert(format!("{version_dir}/{BINARY_NAME}"), Vec::new());
+        state
+            .files
+            .insert(format!("{version_dir}/{INSTALLED_MARKER}"), Vec::new());
+    }
+
+    #[test]
+    fn installs_latest_release_into_version_dir() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET, MAC_ASSET]);
+
+        let path = extension(&host).context_server_binary_path(None).unwrap();
+
+        assert_eq!(path, "github-mcp-server-v1.2.0/github-mcp-server");
+        let state = host.state();
+        assert_eq!(state.files[&path], b"#!/bin/sh\n");
+        assert!(state
+            .files
+            .contains_key("github-mcp-server-v1.2.0/README.md"));
+        assert!(!state
+            .files
+            .contains_key(&format!("github-mcp-server-v1.2.0/{LINUX_ASSET}")));
+        assert!(state.executables.contains(&path));
+    }
+
+    #[test]
+    fn reuses_cached_binary_without_looking_up_release() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        let mut extension = extension(&host);
+        let path = extension.context_server_binary_path(None).unwrap();
+
+        host.state().requests.clear();
+        host.state().latest_release = Some(Err("rate limited".into()));
+
+        assert_eq!(extension.context_server_binary_path(None).unwrap(), path);
+        assert!(host.state().requests.is_empty());
+    }
+
+    #[test]
+    fn selects_asset_for_platform() {
+        let host = FakeHost::default();
+        host.state().platform = Some((zed::Os::Mac, zed::Architecture::Aarch64));
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET, MAC_ASSET]);
+
+        extension(&host).context_server_binary_path(None).unwrap();
+
+        let requests = &host.state().requests;
+        assert!(requests.contains(&format!("get https://example.com/v1.2.0/{MAC_ASSET}")));
+        assert!(!requests.contains(&format!("get https://example.com/v1.2.0/{LINUX_ASSET}")));
+    }
+
+    #[test]
+    fn pinned_version_reuses_install_without_looking_up_release() {
+        let host = FakeHost::default();
+        mark_installed(&host, "v1.0.0");
+
+        let path = extension(&host)
+            .context_server_binary_path(Some("v1.0.0"))
+            .unwrap();
+
+        assert_eq!(path, "github-mcp-server-v1.0.0/github-mcp-server");
+        assert!(host.state().requests.is_empty());
+    }
+
+    #[test]
+    fn pinned_version_is_installed_by_tag_and_not_cached() {
+        let host = FakeHost::default();
+        let release = publish(&host, "v1.0.0", &[LINUX_ASSET]);
+        host.state()
+            .releases_by_tag
+            .insert("v1.0.0".into(), release);
+        let mut extension = extension(&host);
+
+        let path = extension
+            .context_server_binary_path(Some("v1.0.0"))
+            .unwrap();
+
+        assert_eq!(path, "github-mcp-server-v1.0.0/github-mcp-server");
+        assert_eq!(host.state().requests[0], format!("tag {REPO_NAME} v1.0.0"));
+        assert_eq!(extension.cached_binary_path, None);
+    }
+
+    #[test]
+    fn keeps_versions_other_projects_use() {
+        let host = FakeHost::default();
+        mark_installed(&host, "v1.0.0");
+        let mut extension = extension(&host);
+        let pinned = extension
+            .context_server_binary_path(Some("v1.0.0"))
+            .unwrap();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        let latest = extension.context_server_binary_path(None).unwrap();
+        let release = publish(&host, "v1.3.0", &[LINUX_ASSET]);
+        host.state()
+            .releases_by_tag
+            .insert("v1.3.0".into(), release);
+
+        extension
+            .context_server_binary_path(Some("v1.3.0"))
+            .unwrap();
+
+        assert!(host.is_file(&pinned));
+        assert!(host.is_file(&latest));
+        assert_eq!(extension.context_server_binary_path(None).unwrap(), latest);
+    }
+
+    #[test]
+    fn prunes_old_versions_and_keeps_other_files() {
+        let host = FakeHost::default();
+        {
+            let mut state = host.state();
+            state.files.insert(
+                "github-mcp-server-v1.0.0/github-mcp-server".into(),
+                Vec::new(),
+            );
+            state
+                .files
+                .insert("node_modules/mcp-remote/package.json".into(), Vec::new());
+            state
+                .files
+                .insert("trusted_settings.json".into(), Vec::new());
+        }
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+
+        extension(&host).context_server_binary_path(None).unwrap();
+
+        assert_eq!(
+            host.read_work_dir().unwrap(),
+            [
+                "github-mcp-server-v1.2.0",
+                "node_modules",
+                "trusted_settings.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_newest_installed_binary_when_lookup_fails() {
+        let host = FakeHost::default();
+        {
+            let mut state = host.state();
+            // An interrupted install has a binary but was never marked as installed.
+            state.files.insert(
+                "github-mcp-server-v2.0.0/github-mcp-server".into(),
+                Vec::new(),
+            );
+            state.latest_release = Some(Err("rate limited".into()));
+        }
+        for version in ["v1.9.0", "v1.10.0", "v1.2.0"] {
+            mark_installed(&host, version);
+        }
+        let mut extension = extension(&host);
+
+        let path = extension.context_server_binary_path(None).unwrap();
+
+        assert_eq!(path, "github-mcp-server-v1.10.0/github-mcp-server");
+        assert_eq!(extension.cached_binary_path, None);
+    }
+
+    #[test]
+    fn lookup_failure_without_install_is_an_error() {
+        let host = FakeHost::default();
+        host.state().latest_release = Some(Err("rate limited".into()));
+
+        let error = extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert_eq!(error.code(), "release_lookup");
+        assert!(error.to_string().contains("rate limited"));
+    }
+
+    #[test]
+    fn missing_platform_asset_is_an_error() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[MAC_ASSET]);
+
+        let error = extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert_eq!(error.code(), "asset_not_found");
+        assert!(error.to_string().contains(&format!(
+            "no asset for Linux x86_64, available assets: {MAC_ASSET}, \
+             github-mcp-server_v1.2.0_checksums.txt"
+        )));
+        assert!(host.state().files.is_empty());
+    }
+
+    #[test]
+    fn installs_raw_binary_assets() {
+        let host = FakeHost::default();
+        let asset_name = "github-mcp-server-linux-amd64";
+        let mut release = publish(&host, "v1.2.0", &[]);
+        let binary = b"\x7fELF".to_vec();
+        let download_url = format!("https://example.com/v1.2.0/{asset_name}");
+        {
+            let mut state = host.state();
+            state.responses.insert(download_url.clone(), binary.clone());
+            let checksums_url = &release.assets[0].download_url;
+            state.responses.insert(
+                checksums_url.clone(),
+                format!("{}  {asset_name}\n", checksum::sha256_hex(&binary)).into_bytes(),
+            );
+        }
+        release.assets.push(GithubReleaseAsset {
+            name: asset_name.into(),
+            download_url,
+        });
+        host.state().latest_release = Some(Ok(release));
+
+        let path = extension(&host).context_server_binary_path(None).unwrap();
+
+        let state = host.state();
+        assert_eq!(state.files[&path], binary);
+        assert!(state.executables.contains(&path));
+        assert!(!state
+            .files
+            .contains_key(&format!("github-mcp-server-v1.2.0/{asset_name}")));
+    }
+
+    #[test]
+    fn finds_binary_nested_in_archive() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        let archive = tar_gz(&[
+            ("github-mcp-server_Linux_x86_64/README.md", b"readme"),
+            (
+                "github-mcp-server_Linux_x86_64/github-mcp-server",
+                b"nested",
+            ),
+        ]);
+        {
+            let mut state = host.state();
+            state.responses.insert(
+                format!("https://example.com/v1.2.0/{LINUX_ASSET}"),
+                archive.clone(),
+            );
+            state.responses.insert(
+                "https://example.com/v1.2.0/checksums.txt".into(),
+                format!("{}  {LINUX_ASSET}\n", checksum::sha256_hex(&archive)).into_bytes(),
+            );
+        }
+
+        let path = extension(&host).context_server_binary_path(None).unwrap();
+
+        assert_eq!(path, "github-mcp-server-v1.2.0/github-mcp-server");
+        assert_eq!(host.state().files[&path], b"nested");
+    }
+
+    #[test]
+    fn archive_without_binary_is_an_error() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        let archive = tar_gz(&[("README.md", b"readme")]);
+        {
+            let mut state = host.state();
+            state.responses.insert(
+                format!("https://example.com/v1.2.0/{LINUX_ASSET}"),
+                archive.clone(),
+            );
+            state.responses.insert(
+                "https://example.com/v1.2.0/checksums.txt".into(),
+                format!("{}  {LINUX_ASSET}\n", checksum::sha256_hex(&archive)).into_bytes(),
+            );
+        }
+
+        let error = extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert_eq!(error.code(), "download");
+        assert!(error
+            .to_string()
+            .contains(&format!("{LINUX_ASSET} does not contain github-mcp-server")));
+    }
+
+    #[test]
+    fn checksum_mismatch_discards_download() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        host.state().responses.insert(
+            format!("https://example.com/v1.2.0/{LINUX_ASSET}"),
+            tar_gz(&[(BINARY_NAME, b"tampered")]),
+        );
+
+        let error = extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert_eq!(error.code(), "download");
+        assert!(error.to_string().contains("checksum mismatch"));
+        assert!(host.read_work_dir().unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_download_is_an_error() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        host.state()
+            .responses
+            .remove(&format!("https://example.com/v1.2.0/{LINUX_ASSET}"));
+
+        let error = extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert_eq!(error.code(), "download");
+        assert!(!host
+            .state()
+            .files
+            .contains_key("github-mcp-server-v1.2.0/github-mcp-server"));
+    }
+
+    #[test]
+    fn installs_through_staging_dir_and_marks_install_complete() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+
+        extension(&host).context_server_binary_path(None).unwrap();
+
+        assert!(host.is_file("github-mcp-server-v1.2.0/.installed"));
+        assert_eq!(host.read_work_dir().unwrap(), ["github-mcp-server-v1.2.0"]);
+    }
+
+    #[test]
+    fn failed_install_leaves_no_version_or_staging_dir() {
+        let host = FakeHost::default();
+        publish_latest(&host, "v1.2.0", &[LINUX_ASSET]);
+        host.state().responses.insert(
+            format!("https://example.com/v1.2.0/{LINUX_ASSET}"),
+            tar_gz(&[("README.md", b"no binary here")]),
+        );
+
+        extension(&host)
+            .context_server_binary_path(None)
+            .unwrap_err();
+
+        assert!(host.read_work_dir().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reinstalls_version_that_was_never_marked_installed() {
+        let host = FakeHost::default();
+        let release = publish(&host, "v1.2.0", &[LINUX_ASSET]);
+        host.state()
+            .releases_by_tag
+            .insert("v1.2.0".into(), release);
+        // A binary truncated by a crash during an earlier, unstaged install.
+        host.state().files.insert(
+            "github-mcp-server-v1.2.0/github-mcp-server".into(),
+            b"trunc".to_vec(),
+        );
+
+        let path = extension(&host)
+            .context_server_binary_path(Some("v1.2.0"))
+            .unwrap();
+
+        assert_eq!(host.read_file(&path).unwrap(), b"#!/bin/sh\n");
+        assert!(host.is_file("github-mcp-server-v1.2.0/.installed"));
+    }
+
+    #[test]
+    fn sweeps_leftover_staging_dirs_once() {
+        let host = FakeHost::default();
+        mark_installed(&host, "v1.2.0");
+        host.state().files.insert(
+            "staging-github-mcp-server-v1.3.0/github-mcp-server".into(),
+            b"partial".to_vec(),
+        );
+        let mut extension = extension(&host);
+
+        extension
+            .context_server_binary_path(Some("v1.2.0"))
+            .unwrap();
+        assert_eq!(host.read_work_dir().unwrap(), ["github-mcp-server-v1.2.0"]);
+
+        host.state()
+            .dirs
+            .insert("staging-github-mcp-server-v1.4.0".into());
+        extension
+            .context_server_binary_path(Some("v1.2.0"))
+            .unwrap();
+        assert!(host
+            .state()
+            .dirs
+            .contains("staging-github-mcp-server-v1.4.0"));
+    }
+}
@@ -0,0 +1,371 @@
+//! Signing in with GitHub's OAuth device authorization flow.
+//!
+//! `/github-login` requests a device code and shows the user code and verification URL.
+//! Once the user has entered the code, the next `/github-login` or server start exchanges the
+//! device code for a token, which is stored in the extension's work directory.
+
+use crate::error;
+use crate::host::Host;
+use crate::unix_time;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use url::form_urlencoded;
+use zed_extension_api::{serde_json, Result};
+
+const PENDING_LOGIN_FILE: &str = "oauth_device_flow.json";
+const TOKENS_FILE: &str = "oauth_tokens.json";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// The OAuth app and GitHub instance to sign in to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginSettings {
+    /// The web URL of the GitHub instance, e.g. `https://github.com`.
+    pub web_url: String,
+    pub client_id: String,
+    /// Space-separated OAuth scopes to request.
+    pub scopes: String,
+}
+
+/// The result of checking on a device authorization.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LoginStatus {
+    /// The user has authorized the device and the token is stored.
+    SignedIn,
+    /// The user still has to enter `user_code` at `verification_uri`.
+    Pending {
+        user_code: String,
+        verification_uri: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLogin {
+    web_url: String,
+    client_id: String,
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessTokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Continues a pending sign-in for `login`, or starts a new one.
+pub fn login(host: &dyn Host, login: &LoginSettings) -> Result<LoginStatus> {
+    if let Some(status) = poll(host, login)? {
+        return Ok(status);
+    }
+
+    let response: DeviceCodeResponse = post_form(
+        host,
+        &format!("{}/login/device/code", login.web_url),
+        &[("client_id", &login.client_id), ("scope", &login.scopes)],
+    )?;
+    let pending = PendingLogin {
+        web_url: login.web_url.clone(),
+        client_id: login.client_id.clone(),
+        device_code: response.device_code,
+        user_code: response.user_code.clone(),
+        verification_uri: response.verification_uri.clone(),
+        expires_at: unix_time() + response.expires_in,
+    };
+    let pending = serde_json::to_string(&pending).map_err(|e| e.to_string())?;
+    host.write_file(PENDING_LOGIN_FILE, pending.as_bytes())
+        .map_err(|e| format!("failed to write {PENDING_LOGIN_FILE}: {e}"))?;
+
+    Ok(LoginStatus::Pending {
+        user_code: response.user_code,
+        verification_uri: response.verification_uri,
+    })
+}
+
+/// Returns the token stored for `login`, finishing a pending sign-in first if possible.
+pub fn token(host: &dyn Host, login: &LoginSettings) -> Result<Option<String>> {
+    let stored = read_tokens(host).remove(&token_key(login));
+    match poll(host, login) {
+        Ok(_) => Ok(read_tokens(host).remove(&token_key(login))),
+        Err(err) if stored.is_some() => {
+            error::warn(
+                &format!("failed to finish the pending GitHub sign-in: {err}"),
+                stored.as_slice(),
+            );
+            Ok(stored)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Checks on the pending sign-in for `login`, if there is one that hasn't expired.
+fn poll(host: &dyn Host, login: &LoginSettings) -> Result<Option<LoginStatus>> {
+    let pending = host
+        .read_file(PENDING_LOGIN_FILE)
+        .ok()
+        .and_then(|pending| serde_json::from_slice::<PendingLogin>(&pending).ok())
+        .filter(|pending| {
+            pending.web_url == login.web_url
+                && pending.client_id == login.client_id
+                && pending.expires_at > unix_time()
+        });
+    let Some(pending) = pending else {
+        return Ok(None);
+    };
+
+    let response: AccessTokenResponse = post_form(
+        host,
+        &format!("{}/login/oauth/access_token", login.web_url),
+        &[
+            ("client_id", &login.client_id),
+            ("device_code", &pending.device_code),
+            ("grant_type", DEVICE_GRANT_TYPE),
+        ],
+    )?;
+
+    if let Some(token) = response.access_token {
+        let mut tokens = read_tokens(host);
+        tokens.insert(token_key(login), token);
+        let tokens = serde_json::to_string(&tokens).map_err(|e| e.to_string())?;
+        host.write_file(TOKENS_FILE, tokens.as_bytes())
+            .map_err(|e| format!("failed to write {TOKENS_FILE}: {e}"))?;
+        host.remove_file(PENDING_LOGIN_FILE).ok();
+        return Ok(Some(LoginStatus::SignedIn));
+    }
+
+    match response.error.as_deref() {
+        Some("authorization_pending" | "slow_down") => Ok(Some(LoginStatus::Pending {
+            user_code: pending.user_code,
+            verification_uri: pending.verification_uri,
+        })),
+        Some("expired_token" | "access_denied") => {
+            host.remove_file(PENDING_LOGIN_FILE).ok();
+            Ok(None)
+        }
+        error => Err(format!(
+            "GitHub sign-in failed: {}",
+            response
+                .error_description
+                .as_deref()
+                .or(error)
+                .unwrap_or("no token in response")
+        )),
+    }
+}
+
+fn post_form<T: DeserializeOwned>(
+    host: &dyn Host,
+    url: &str,
+    fields: &[(&str, &str)],
+) -> Result<T> {
+    let form = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(fields)
+        .finish();
+    let body = host
+        .post_form(url, &form)
+        .map_err(|e| format!("POST {url} failed: {e}"))?;
+    serde_json::from_slice(&body).map_err(|e| format!("unexpected response from POST {url}: {e}"))
+}
+
+fn token_key(login: &LoginSettings) -> String {
+    format!("{} {}", login.web_url, login.client_id)
+}
+
+fn read_tokens(host: &dyn Host) -> HashMap<String, String> {
+    host.read_file(TOKENS_FILE)
+        .ok()
+        .and_then(|tokens| serde_json::from_slice(&tokens).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::fake::FakeHost;
+
+    const DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+    const ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+
+    fn settings() -> LoginSettings {
+        LoginSettings {
+            web_url: "https://github.com".into(),
+            client_id: "Iv1.client".into(),
+            scopes: "repo read:org".into(),
+        }
+    }
+
+    fn respond(host: &FakeHost, url: &str, body: &str) {
+        host.state()
+            .responses
+            .insert(url.to_string(), body.as_bytes().to_vec());
+    }
+
+    /// Leaves a sign-in waiting for the user to enter `WDJB-MJHT`.
+    fn start_login(host: &FakeHost) {
+        let pending = PendingLogin {
+            web_url: "https://github.com".into(),
+            client_id: "Iv1.client".into(),
+            device_code: "device-code".into(),
+            user_code: "WDJB-MJHT".into(),
+            verification_uri: "https://github.com/login/device".into(),
+            expires_at: unix_time() + 900,
+        };
+        host.write_file(
+            PENDING_LOGIN_FILE,
+            serde_json::to_string(&pending).unwrap().as_bytes(),
+        )
+        .unwrap();
+    }
+
+    fn pending() -> LoginStatus {
+        LoginStatus::Pending {
+            user_code: "WDJB-MJHT".into(),
+            verification_uri: "https://github.com/login/device".into(),
+        }
+    }
+
+    #[test]
+    fn requests_device_code() {
+        let host = FakeHost::default();
+        respond(
+            &host,
+            DEVICE_CODE_URL,
+            r#"{"device_code":"device-code","user_code":"WDJB-MJHT",
+                "verification_uri":"https://github.com/login/device","expires_in":900,
+                "interval":5}"#,
+        );
+
+        assert_eq!(login(&host, &settings()).unwrap(), pending());
+        assert_eq!(
+            host.state().requests,
+            [format!(
+                "post {DEVICE_CODE_URL} client_id=Iv1.client&scope=repo+read%3Aorg"
+            )]
+        );
+        assert!(host.is_file(PENDING_LOGIN_FILE));
+    }
+
+    #[test]
+    fn authorization_pending_keeps_waiting() {
+        for error in ["authorization_pending", "slow_down"] {
+            let host = FakeHost::default();
+            start_login(&host);
+            respond(
+                &host,
+                ACCESS_TOKEN_URL,
+                &format!(r#"{{"error":"{error}"}}"#),
+            );
+
+            assert_eq!(login(&host, &settings()).unwrap(), pending(), "{error}");
+            assert_eq!(token(&host, &settings()).unwrap(), None, "{error}");
+            assert!(host.is_file(PENDING_LOGIN_FILE), "{error}");
+        }
+    }
+
+    #[test]
+    fn expired_device_code_starts_over() {
+        let host = FakeHost::default();
+        start_login(&host);
+        respond(&host, ACCESS_TOKEN_URL, r#"{"error":"expired_token"}"#);
+        respond(
+            &host,
+            DEVICE_CODE_URL,
+            r#"{"device_code":"new-device-code","user_code":"NEW-CODE",
+                "verification_uri":"https://github.com/login/device","expires_in":900}"#,
+        );
+
+        let status = login(&host, &settings()).unwrap();
+
+        assert_eq!(
+            status,
+            LoginStatus::Pending {
+                user_code: "NEW-CODE".into(),
+                verification_uri: "https://github.com/login/device".into(),
+            }
+        );
+        let pending = host.read_file(PENDING_LOGIN_FILE).unwrap();
+        assert!(String::from_utf8(pending)
+            .unwrap()
+            .contains("new-device-code"));
+    }
+
+    #[test]
+    fn stores_token_once_authorized() {
+        let host = FakeHost::default();
+        start_login(&host);
+        respond(
+            &host,
+            ACCESS_TOKEN_URL,
+            r#"{"access_token":"gho_token","token_type":"bearer","scope":"repo"}"#,
+        );
+
+        assert_eq!(
+            token(&host, &settings()).unwrap().as_deref(),
+            Some("gho_token")
+        );
+        assert!(!host.is_file(PENDING_LOGIN_FILE));
+        assert_eq!(
+            host.state().requests,
+            [format!(
+                "post {ACCESS_TOKEN_URL} client_id=Iv1.client&device_code=device-code&\
+                 grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"
+            )]
+        );
+    }
+
+    #[test]
+    fn stored_token_survives_failed_poll() {
+        let host = FakeHost::default();
+        let tokens = HashMap::from([(token_key(&settings()), "gho_token".to_string())]);
+        host.write_file(
+            TOKENS_FILE,
+            serde_json::to_string(&tokens).unwrap().as_bytes(),
+        )
+        .unwrap();
+        // The access token endpoint is unreachable.
+        start_login(&host);
+
+        assert_eq!(
+            token(&host, &settings()).unwrap().as_deref(),
+            Some("gho_token")
+        );
+    }
+
+    #[test]
+    fn failed_poll_without_stored_token_is_an_error() {
+        let host = FakeHost::default();
+        start_login(&host);
+
+        let error = token(&host, &settings()).unwrap_err();
+
+        assert!(error.contains("POST https://github.com/login/oauth/access_token failed"));
+    }
+
+    #[test]
+    fn denied_authorization_is_reported_by_description() {
+        let host = FakeHost::default();
+        start_login(&host);
+        respond(
+            &host,
+            ACCESS_TOKEN_URL,
+            r#"{"error":"unsupported_grant_type","error_description":"The grant type is not supported."}"#,
+        );
+
+        let error = token(&host, &settings()).unwrap_err();
+
+        assert_eq!(
+            error,
+            "GitHub sign-in failed: The grant type is not supported."
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Identification of the GitHub repository a project belongs to.
+
+use std::fmt;
+use url::Url;
+
+/// The remotes to prefer when a repository has several, most preferred first.
+const PREFERRED_REMOTES: [&str; 2] = ["origin", "upstream"];
+
+/// A GitHub repository, e.g. `zed-industries/zed` on `github.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+}
+
+impl Repository {
+    /// Parses `owner/repo` shorthand or an HTTPS, SSH or scp-style git remote URL.
+    ///
+    /// Shorthand is assumed to live on `default_host`.
+    pub fn parse(value: &str, default_host: &str) -> Option<Self> {
+        let value = value.trim();
+
+        let (host, path) = if value.contains("://") {
+            let url = Url::parse(value).ok()?;
+            (url.host_str()?.to_string(), url.path().to_string())
+        } else if let Some((user_host, path)) = value
+            .split_once(':')
+            .filter(|(user_host, _)| !user_host.contains('/'))
+        {
+            // scp-style, e.g. `git@github.com:owner/repo.git` or `github.com:owner/repo`.
+            let host = user_host
+                .rsplit('@')
+                .next()
+                .filter(|host| !host.is_empty())?;
+            (host.to_string(), path.to_string())
+        } else {
+            (default_host.to_string(), value.to_string())
+        };
+
+        let mut segments = path.trim_matches('/').split('/');
+        let owner = segments.next().filter(|owner| !owner.is_empty())?;
+        let name = segments.next()?.trim_end_matches(".git");
+        if name.is_empty() || segments.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_ascii_lowercase(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Finds the GitHub repository among the remotes in a `.git/config` file.
+    ///
+    /// Only remotes on `host` are considered; `origin` wins over `upstream`, which wins over
+    /// any other remote.
+    pub fn from_git_config(config: &str, host: &str) -> Option<Self> {
+        let mut remotes = Vec::new();
+        let mut current_remote = None;
+        for line in config.lines().map(str::trim) {
+            if line.starts_with('[') {
+                current_remote = line
+                    .strip_prefix("[remote \"")
+                    .and_then(|rest| rest.strip_suffix("\"]"))
+                    .map(str::to_string);
+            } else if let Some(remote) = &current_remote {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                if key.trim() != "url" {
+                    continue;
+                }
+                if let Some(repository) = Self::parse(value, host) {
+                    if repository.host.eq_ignore_ascii_case(host) {
+                        remotes.push((remote.clone(), repository));
+                    }
+                }
+            }
+        }
+
+        remotes
+            .into_iter()
+            .min_by_key(|(remote, _)| {
+                PREFERRED_REMOTES
+                    .iter()
+                    .position(|preferred| preferred == remote)
+                    .unwrap_or(PREFERRED_REMOTES.len())
+            })
+            .map(|(_, repository)| repository)
+    }
+}
+
+impl fmt::Display for Repository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(host: &str, owner: &str, name: &str) -> Option<Repository> {
+        Some(Repository {
+            host: host.to_string(),
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn parses_remote_urls_and_shorthand() {
+        for (value, expected) in [
+            (
+                "zed-industries/zed",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "https://github.com/zed-industries/zed.git",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "https://GitHub.com/zed-industries/zed/",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "ssh://git@github.com:22/zed-industries/zed.git",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "git@github.com:zed-industries/zed.git",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "github.com:zed-industries/zed.git",
+                ("github.com", "zed-industries", "zed"),
+            ),
+            (
+                "git@github.example.com:platform/api",
+                ("github.example.com", "platform", "api"),
+            ),
+            (
+                "https://github.example.com/platform/api",
+                ("github.example.com", "platform", "api"),
+            ),
+        ] {
+            let (host, owner, name) = expected;
+            assert_eq!(
+                Repository::parse(value, "github.com"),
+                repository(host, owner, name),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn shorthand_uses_the_default_host() {
+        assert_eq!(
+            Repository::parse("platform/api", "github.example.com"),
+            repository("github.example.com", "platform", "api")
+        );
+    }
+
+    #[test]
+    fn rejects_paths_that_are_not_a_repository() {
+        for value in [
+            "",
+            "zed",
+            "zed-industries/zed/issues",
+            "https://github.com/zed-industries",
+            ":zed-industries/zed",
+            "git@:zed-industries/zed",
+            "/zed.git",
+        ] {
+            assert_eq!(Repository::parse(value, "github.com"), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn prefers_origin_then_upstream_on_the_host() {
+        let config = r#"
+            [core]
+                bare = false
+            [remote "fork"]
+                url = git@github.com:someone/zed.git
+            [remote "upstream"]
+                url = https://github.com/zed-industries/zed.git
+            [remote "origin"]
+                url = https://gitlab.com/someone/zed.git
+        "#;
+
+        assert_eq!(
+            Repository::from_git_config(config, "github.com"),
+            repository("github.com", "zed-industries", "zed")
+        );
+
+        let config = format!("{config}\n[remote \"origin\"]\n    url = github.com:someone/zed\n");
+        assert_eq!(
+            Repository::from_git_config(&config, "github.com"),
+            repository("github.com", "someone", "zed")
+        );
+        assert_eq!(
+            Repository::from_git_config(&config, "github.example.com"),
+            None
+        );
+    }
+}
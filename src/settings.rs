@@ -0,0 +1,764 @@
+//! The settings accepted under `context_servers.mcp-server-github.settings`.
+
+use crate::github_app::GitHubApp;
+use crate::repository::Repository;
+use crate::token::TokenSource;
+use crate::TOKEN_ENV_VAR;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::Url;
+use zed_extension_api::{process, Result};
+
+const DEFAULT_HOSTNAME: &str = "github.com";
+const DEFAULT_IMAGE: &str = "ghcr.io/github/github-mcp-server";
+const DEFAULT_REMOTE_URL: &str = "https://api.githubcopilot.com/mcp/";
+/// The variable github-mcp-server reads `--read-only` from when the flag isn't passed.
+const READ_ONLY_ENV_VAR: &str = "GITHUB_READ_ONLY";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitHubContextServerSettings {
+    #[serde(flatten)]
+    pub token: TokenSettings,
+    /// The github-mcp-server release to run, e.g. "v0.5.0". Defaults to "latest".
+    pub server_version: Option<String>,
+    /// Run a github-mcp-server executable you provide instead of downloading a release.
+    pub binary: Option<BinarySettings>,
+    /// The toolsets to enable, e.g. `["repos", "issues", "pull_requests"]`. Leave empty to
+    /// use the server's default toolsets.
+    #[serde(default)]
+    pub toolsets: Vec<Toolset>,
+    /// Only expose tools that read from GitHub, so the agent cannot merge, push, comment or
+    /// close anything. Recommended as a starting point.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Start with a small set of meta-tools and let the agent enable toolsets on demand.
+    /// Toolsets listed in `toolsets` are enabled from the start.
+    #[serde(default)]
+    pub dynamic_toolsets: bool,
+    /// The GitHub instance to connect to. Leave unset for github.com.
+    ///
+    /// For GitHub Enterprise Server use your instance URL, e.g. `https://github.example.com`.
+    /// For GitHub Enterprise Cloud with data residency use your ghe.com subdomain, e.g.
+    /// `https://octocorp.ghe.com`.
+    pub host: Option<String>,
+    /// Extra arguments appended after the `stdio` subcommand, e.g. `["--enable-command-logging"]`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Extra environment variables for the server. Variables managed by other settings, such as
+    /// `GITHUB_HOST`, take precedence; the token variable cannot be set here.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The repository the `/github-repo` command reports, as `owner/repo` or a git remote URL.
+    /// Defaults to the repository of the project's `origin` or `upstream` remote.
+    pub default_repository: Option<String>,
+    /// Replacement descriptions for tools, keyed by tool name, e.g.
+    /// `{ "create_issue": "Only use for bugs reported by the user." }`.
+    #[serde(default)]
+    pub tool_descriptions: HashMap<String, String>,
+    /// How to run the server: a downloaded native `binary`, or a `docker` container.
+    #[serde(default)]
+    pub runtime: Runtime,
+    /// The container image to run when `runtime` is `docker`. Append `@sha256:<digest>` to pin
+    /// it. Defaults to `ghcr.io/github/github-mcp-server`.
+    pub image: Option<String>,
+    /// The container CLI to run the image with when `runtime` is `docker`.
+    #[serde(default)]
+    pub container_cli: ContainerCli,
+    /// Where the server runs: a `local` process, or GitHub's hosted `remote` MCP server.
+    /// In remote mode only the token, `host`, `read_only`, `toolsets` and `env` settings apply.
+    #[serde(default)]
+    pub mode: Mode,
+    /// The MCP endpoint to connect to when `mode` is `remote`. Defaults to
+    /// `https://api.githubcopilot.com/mcp/`, or the Copilot API of your ghe.com `host`.
+    pub remote_url: Option<String>,
+    /// Named GitHub accounts, e.g. `{ "work": { "token_env": "WORK_GITHUB_TOKEN" } }`.
+    /// Settings in the selected profile override the ones above.
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// The profile to use. When unset, the first profile (by name) whose `matches` patterns
+    /// match `default_repository` is used, if any. The project's git remote is not matched,
+    /// since servers start without access to the project's files.
+    pub active_profile: Option<String>,
+    /// Warn when the token expires in fewer than this many days. Defaults to 7.
+    pub token_expiry_warning_days: Option<u32>,
+    /// The client ID of a GitHub OAuth app to sign in with `/github-login` when no token source
+    /// is configured. The app must have device flow enabled.
+    pub oauth_client_id: Option<String>,
+    /// The ID of a GitHub App to authenticate as, instead of as a person. Requires
+    /// `installation_id` and `private_key_path`, and takes precedence over other token sources.
+    pub app_id: Option<u64>,
+    /// The ID of the GitHub App's installation on the account or organization to access.
+    pub installation_id: Option<u64>,
+    /// The path to the GitHub App's PEM-encoded private key.
+    pub private_key_path: Option<String>,
+}
+
+/// Where to read the GitHub token from.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema)]
+pub struct TokenSettings {
+    /// Your GitHub Personal Access Token, stored in plaintext.
+    ///
+    /// Token sources are checked in order: `github_personal_access_token`, `token_env`,
+    /// `token_file`, `token_command`. The first one that is set is used.
+    pub github_personal_access_token: Option<String>,
+    /// The name of an environment variable holding the token.
+    pub token_env: Option<String>,
+    /// The path to a file holding the token. Surrounding whitespace is trimmed.
+    pub token_file: Option<String>,
+    /// A shell command that prints the token, e.g. `gh auth token`.
+    pub token_command: Option<String>,
+}
+
+impl TokenSettings {
+    /// Returns the highest-precedence configured token source.
+    pub fn source(&self) -> Option<TokenSource<'_>> {
+        self.github_personal_access_token
+            .as_deref()
+            .map(TokenSource::Plaintext)
+            .or(self.token_env.as_deref().map(TokenSource::Env))
+            .or(self.token_file.as_deref().map(TokenSource::File))
+            .or(self.token_command.as_deref().map(TokenSource::Command))
+    }
+}
+
+/// A named GitHub account and the settings that go with it.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Profile {
+    #[serde(flatten)]
+    pub token: TokenSettings,
+    /// The GitHub instance this account lives on. Defaults to the top-level `host`.
+    pub host: Option<String>,
+    /// The toolsets to enable for this account. Defaults to the top-level `toolsets`.
+    pub toolsets: Option<Vec<Toolset>>,
+    /// Whether this account is read-only. Defaults to the top-level `read_only`.
+    pub read_only: Option<bool>,
+    /// Repositories this profile is selected for, as `owner`, `owner/repo`, `host/owner` or
+    /// `host/owner/repo` patterns where `*` matches anything, e.g. `["my-org/*"]`. Only
+    /// `default_repository` is matched against them, not the project's git remote.
+    #[serde(default)]
+    pub matches: Vec<String>,
+}
+
+impl Profile {
+    /// Returns whether any of the `matches` patterns match `repository`.
+    fn matches(&self, repository: &Repository) -> bool {
+        let Repository { host, owner, name } = repository;
+        let candidates = [
+            owner.clone(),
+            format!("{owner}/{name}"),
+            format!("{host}/{owner}"),
+            format!("{host}/{owner}/{name}"),
+        ];
+        self.matches.iter().any(|pattern| {
+            candidates.iter().any(|candidate| {
+                glob_match(
+                    &pattern.to_ascii_lowercase(),
+                    &candidate.to_ascii_lowercase(),
+                )
+            })
+        })
+    }
+}
+
+/// Whether `arg` sets github-mcp-server's `--read-only` flag to anything but true.
+fn disables_read_only(arg: &str) -> bool {
+    let flag = arg.trim_start_matches('-');
+    if flag.len() == arg.len() {
+        return false;
+    }
+    match flag.strip_prefix("read-only=") {
+        // The values Go's `strconv.ParseBool` reads as true.
+        Some(value) => !matches!(value, "1" | "t" | "T" | "true" | "TRUE" | "True"),
+        None => arg == "-read-only",
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Runtime {
+    #[default]
+    Binary,
+    Docker,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerCli {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerCli {
+    pub fn command(&self) -> &'static str {
+        match self {
+            ContainerCli::Docker => "docker",
+            ContainerCli::Podman => "podman",
+        }
+    }
+}
+
+/// A group of related github-mcp-server tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Toolset {
+    All,
+    Context,
+    Actions,
+    CodeSecurity,
+    Dependabot,
+    Discussions,
+    Experiments,
+    Gists,
+    Issues,
+    Notifications,
+    Orgs,
+    Projects,
+    PullRequests,
+    Repos,
+    SecretProtection,
+    SecurityAdvisories,
+    Users,
+}
+
+impl Toolset {
+    /// Returns the name github-mcp-server uses for this toolset.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Toolset::All => "all",
+            Toolset::Context => "context",
+            Toolset::Actions => "actions",
+            Toolset::CodeSecurity => "code_security",
+            Toolset::Dependabot => "dependabot",
+            Toolset::Discussions => "discussions",
+            Toolset::Experiments => "experiments",
+            Toolset::Gists => "gists",
+            Toolset::Issues => "issues",
+            Toolset::Notifications => "notifications",
+            Toolset::Orgs => "orgs",
+            Toolset::Projects => "projects",
+            Toolset::PullRequests => "pull_requests",
+            Toolset::Repos => "repos",
+            Toolset::SecretProtection => "secret_protection",
+            Toolset::SecurityAdvisories => "security_advisories",
+            Toolset::Users => "users",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BinarySettings {
+    /// The path to the github-mcp-server executable.
+    pub path: String,
+    /// The arguments to launch it with. Defaults to `["stdio"]`.
+    pub arguments: Option<Vec<String>>,
+}
+
+impl BinarySettings {
+    /// Checks that the configured executable can actually be launched.
+    ///
+    /// The extension sandbox cannot inspect files outside its own work directory, so
+    /// the binary is probed with `--version` instead of being `stat`ed.
+    pub fn validate(&self) -> Result<()> {
+        let output = process::Command::new(&self.path)
+            .arg("--version")
+            .output()
+            .map_err(|e| {
+                format!(
+                    "`binary.path` '{}' does not exist or is not executable: {e}",
+                    self.path
+                )
+            })?;
+
+        if output.status != Some(0) {
+            return Err(format!(
+                "`binary.path` '{}' failed to run `--version` (status {:?}): {}",
+                self.path,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl GitHubContextServerSettings {
+    /// Applies the active or matching profile on top of the top-level settings.
+    ///
+    /// Returns the name of the applied profile, if any.
+    pub fn apply_profile(&mut self) -> Result<Option<String>> {
+        let name = match self.active_profile.as_deref() {
+            Some(name) => {
+                if !self.profiles.contains_key(name) {
+                    let mut known: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+                    known.sort();
+                    return Err(format!(
+                        "`active_profile` '{name}' is not defined in `profiles` (known: {})",
+                        known.join(", ")
+                    ));
+                }
+                name.to_string()
+            }
+            None => {
+                // The profile may change `host`, so the repository's host is only checked
+                // against it once the profile is applied.
+                let Some(repository) = self.parse_default_repository()? else {
+                    return Ok(None);
+                };
+                let mut names: Vec<_> = self.profiles.keys().collect();
+                names.sort();
+                let Some(name) = names
+                    .into_iter()
+                    .find(|name| self.profiles[*name].matches(&repository))
+                else {
+                    return Ok(None);
+                };
+                name.clone()
+            }
+        };
+
+        let profile = self.profiles[&name].clone();
+        if profile.token.source().is_some() {
+            self.token = profile.token;
+        }
+        if profile.host.is_some() {
+            self.host = profile.host;
+        }
+        if let Some(toolsets) = profile.toolsets {
+            self.toolsets = toolsets;
+        }
+        if let Some(read_only) = profile.read_only {
+            self.read_only = read_only;
+        }
+        self.default_repository()?;
+        Ok(Some(name))
+    }
+
+    /// Rejects toolset configurations that `dynamic_toolsets` would make meaningless.
+    pub fn validate_toolsets(&self) -> Result<()> {
+        if self.dynamic_toolsets && self.toolsets.contains(&Toolset::All) {
+            return Err(
+                "`dynamic_toolsets` cannot be combined with the `all` toolset, which enables \
+                 every toolset up front; remove one of them"
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the validated endpoint to connect to in `remote` mode.
+    pub fn remote_url(&self) -> Result<String> {
+        if self.binary.is_some() || self.runtime != Runtime::Binary {
+            return Err(
+                "`\"mode\": \"remote\"` cannot be combined with `binary` or `runtime`, remove them"
+                    .into(),
+            );
+        }
+
+        if let Some(remote_url) = self.remote_url.as_deref().map(str::trim) {
+            let url = Url::parse(remote_url)
+                .map_err(|e| format!("`remote_url` '{remote_url}' is not a valid URL ({e})"))?;
+            if !matches!(url.scheme(), "https" | "http") {
+                return Err(format!(
+                    "`remote_url` '{remote_url}' must be an http(s) URL"
+                ));
+            }
+            return Ok(remote_url.to_string());
+        }
+
+        let hostname = self.github_hostname()?;
+        if hostname == DEFAULT_HOSTNAME {
+            Ok(DEFAULT_REMOTE_URL.to_string())
+        } else if let Some(subdomain) = hostname.strip_suffix(".ghe.com") {
+            Ok(format!("https://copilot-api.{subdomain}.ghe.com/mcp"))
+        } else {
+            Err(format!(
+                "GitHub does not host a remote MCP server for {hostname}, set `remote_url` \
+                 or use `\"mode\": \"local\"`"
+            ))
+        }
+    }
+
+    /// Returns the headers that carry `read_only` and `toolsets` to the remote server.
+    pub fn remote_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if self.read_only {
+            headers.push(("X-MCP-Readonly".to_string(), "true".to_string()));
+        }
+        if let Some(toolsets) = self.toolsets_env() {
+            headers.push(("X-MCP-Toolsets".to_string(), toolsets));
+        }
+        headers
+    }
+
+    /// Returns the validated container image to run in `docker` mode.
+    pub fn container_image(&self) -> Result<String> {
+        if self.binary.is_some() {
+            return Err(
+                "`binary` cannot be used with `\"runtime\": \"docker\"`, remove one of them".into(),
+            );
+        }
+
+        let image = self
+            .image
+            .as_deref()
+            .map(str::trim)
+            .filter(|image| !image.is_empty())
+            .unwrap_or(DEFAULT_IMAGE);
+        if let Some((_, digest)) = image.split_once('@') {
+            let valid = digest
+                .strip_prefix("sha256:")
+                .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+            if !valid {
+                return Err(format!(
+                    "`image` '{image}' has an invalid digest, expected `@sha256:` followed by 64 hex digits"
+                ));
+            }
+        }
+        Ok(image.to_string())
+    }
+
+    /// Returns the value for `GITHUB_TOOLSETS`, or `None` to use the server's defaults.
+    pub fn toolsets_env(&self) -> Option<String> {
+        if self.toolsets.is_empty() {
+            return None;
+        }
+
+        let names: Vec<_> = self.toolsets.iter().map(Toolset::as_str).collect();
+        Some(names.join(","))
+    }
+
+    /// Returns the validated value for `GITHUB_HOST`, or `None` to use github.com.
+    pub fn github_host(&self) -> Result<Option<String>> {
+        let Some(host) = self
+            .host
+            .as_deref()
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let url = Url::parse(host).map_err(|e| {
+            format!("`host` '{host}' is not a valid URL ({e}), expected e.g. https://github.example.com")
+        })?;
+        if !matches!(url.scheme(), "https" | "http") || url.host_str().is_none() {
+            return Err(format!(
+                "`host` '{host}' must be an http(s) URL, e.g. https://github.example.com"
+            ));
+        }
+        if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+            return Err(format!(
+                "`host` '{host}' must not include a path, e.g. https://github.example.com"
+            ));
+        }
+
+        Ok(Some(host.trim_end_matches('/').to_string()))
+    }
+
+    /// Returns the `GITHUB_MCP_TOOL_<NAME>_DESCRIPTION` overrides for `tool_descriptions`.
+    pub fn tool_description_env(&self) -> Result<Vec<(String, String)>> {
+        let mut env = self
+            .tool_descriptions
+            .iter()
+            .map(|(tool, description)| {
+                if tool.is_empty() || !tool.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!(
+                        "`tool_descriptions` key '{tool}' is not a tool name, e.g. `create_issue`"
+                    ));
+                }
+                Ok((
+                    format!("GITHUB_MCP_TOOL_{}_DESCRIPTION", tool.to_ascii_uppercase()),
+                    description.clone(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        env.sort();
+        Ok(env)
+    }
+
+    /// Returns the web URL of the configured GitHub instance, e.g. `https://github.com`.
+    pub fn web_url(&self) -> Result<String> {
+        Ok(self
+            .github_host()?
+            .unwrap_or_else(|| format!("https://{DEFAULT_HOSTNAME}")))
+    }
+
+    /// Returns the page where tokens for the configured GitHub instance are managed.
+    pub fn token_settings_url(&self) -> Result<String> {
+        Ok(format!("{}/settings/tokens", self.web_url()?))
+    }
+
+    /// Returns the REST API base URL of the configured GitHub instance.
+    pub fn api_url(&self) -> Result<String> {
+        let hostname = self.github_hostname()?;
+        if hostname == DEFAULT_HOSTNAME {
+            return Ok("https://api.github.com".to_string());
+        }
+        if hostname.ends_with(".ghe.com") {
+            return Ok(format!("https://api.{hostname}"));
+        }
+        let host = self.github_host()?.unwrap_or_default();
+        Ok(format!("{host}/api/v3"))
+    }
+
+    /// Returns the host name of the configured GitHub instance, e.g. `github.com`.
+    pub fn github_hostname(&self) -> Result<String> {
+        let Some(host) = self.github_host()? else {
+            return Ok(DEFAULT_HOSTNAME.to_string());
+        };
+        let url = Url::parse(&host).map_err(|e| e.to_string())?;
+        Ok(url
+            .host_str()
+            .unwrap_or(DEFAULT_HOSTNAME)
+            .to_ascii_lowercase())
+    }
+
+    /// Returns the repository configured with `default_repository`, if any.
+    pub fn default_repository(&self) -> Result<Option<Repository>> {
+        let Some(repository) = self.parse_default_repository()? else {
+            return Ok(None);
+        };
+
+        let hostname = self.github_hostname()?;
+        if repository.host != hostname {
+            let value = self.default_repository.as_deref().unwrap_or_default();
+            return Err(format!(
+                "`default_repository` '{value}' is on {}, but the server is configured for {hostname}",
+                repository.host
+            ));
+        }
+        Ok(Some(repository))
+    }
+
+    /// Parses `default_repository` without checking that it is on the configured host.
+    fn parse_default_repository(&self) -> Result<Option<Repository>> {
+        let Some(value) = self.default_repository.as_deref() else {
+            return Ok(None);
+        };
+
+        let hostname = self.github_hostname()?;
+        Repository::parse(value, &hostname)
+            .map(Some)
+            .ok_or_else(|| {
+                format!(
+                    "`default_repository` '{value}' is not an `owner/repo` name or git remote URL"
+                )
+            })
+    }
+
+    /// Rejects pass-through arguments and variables that would clobber the subcommand or token.
+    pub fn validate_passthrough(&self) -> Result<()> {
+        if self.extra_args.iter().any(|arg| arg == "stdio") {
+            return Err(
+                "`extra_args` must not contain the `stdio` subcommand, it is always passed".into(),
+            );
+        }
+        if let Some(name) = self
+            .env
+            .keys()
+            .find(|name| name.eq_ignore_ascii_case(TOKEN_ENV_VAR))
+        {
+            return Err(format!(
+                "`env` must not set {name}, configure the token with `github_personal_access_token`, \
+                 `token_env`, `token_file` or `token_command` instead"
+            ));
+        }
+        if self.read_only {
+            // `--read-only` is passed before `extra_args`, so a later `--read-only=false`
+            // would win.
+            let custom_args = self
+                .binary
+                .iter()
+                .flat_map(|binary| binary.arguments.iter());
+            if let Some(arg) = custom_args
+                .flatten()
+                .chain(&self.extra_args)
+                .find(|arg| disables_read_only(arg))
+            {
+                return Err(format!(
+                    "`{arg}` would turn off read-only mode, remove it and use `read_only` instead"
+                ));
+            }
+            if let Some(name) = self
+                .env
+                .keys()
+                .find(|name| name.eq_ignore_ascii_case(READ_ONLY_ENV_VAR))
+            {
+                return Err(format!(
+                    "`env` must not set {name} while the server is read-only, use `read_only` \
+                     instead"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the GitHub App to authenticate as, if one is configured.
+    pub fn github_app(&self) -> Result<Option<GitHubApp>> {
+        match (self.app_id, self.installation_id, &self.private_key_path) {
+            (Some(app_id), Some(installation_id), Some(private_key_path)) => Ok(Some(GitHubApp {
+                app_id,
+                installation_id,
+                private_key_path: private_key_path.clone(),
+            })),
+            (None, None, None) => Ok(None),
+            _ => Err(
+                "`app_id`, `installation_id` and `private_key_path` must be set together to \
+                 authenticate as a GitHub App"
+                    .into(),
+            ),
+        }
+    }
+
+    /// Returns the release tag to pin to, or `None` to track the latest release.
+    pub fn pinned_release_tag(&self) -> Option<String> {
+        let version = self.server_version.as_deref()?.trim();
+        if version.is_empty() || version.eq_ignore_ascii_case("latest") {
+            return None;
+        }
+
+        Some(if version.starts_with('v') {
+            version.to_string()
+        } else {
+            format!("v{version}")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::{self, json};
+
+    fn settings(value: serde_json::Value) -> GitHubContextServerSettings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn with_work_profile(default_repository: &str) -> GitHubContextServerSettings {
+        settings(json!({
+            "token_env": "PERSONAL_GITHUB_TOKEN",
+            "default_repository": default_repository,
+            "profiles": {
+                "work": {
+                    "token_env": "WORK_GITHUB_TOKEN",
+                    "host": "https://github.example.com",
+                    "matches": ["github.example.com/*"],
+                },
+                "personal": {
+                    "matches": ["octocat/*"],
+                },
+            },
+        }))
+    }
+
+    #[test]
+    fn selects_profile_on_another_host_by_default_repository() {
+        let mut settings = with_work_profile("https://github.example.com/org/repo");
+
+        assert_eq!(settings.apply_profile().unwrap().as_deref(), Some("work"));
+        assert_eq!(settings.github_hostname().unwrap(), "github.example.com");
+        assert_eq!(
+            settings.token.token_env.as_deref(),
+            Some("WORK_GITHUB_TOKEN")
+        );
+        let repository = settings.default_repository().unwrap().unwrap();
+        assert_eq!(repository.host, "github.example.com");
+    }
+
+    #[test]
+    fn selects_profile_by_owner() {
+        let mut settings = with_work_profile("octocat/hello-world");
+
+        assert_eq!(
+            settings.apply_profile().unwrap().as_deref(),
+            Some("personal")
+        );
+        assert_eq!(settings.github_hostname().unwrap(), "github.com");
+    }
+
+    #[test]
+    fn repository_on_another_host_without_matching_profile_is_an_error() {
+        let mut settings = with_work_profile("https://gitlab.example.com/org/repo");
+
+        assert_eq!(settings.apply_profile().unwrap(), None);
+        assert!(settings
+            .default_repository()
+            .unwrap_err()
+            .contains("is on gitlab.example.com, but the server is configured for github.com"));
+    }
+
+    #[test]
+    fn read_only_server_rejects_flags_that_turn_it_off() {
+        for value in [
+            json!({ "read_only": true, "extra_args": ["--read-only=false"] }),
+            json!({ "read_only": true, "extra_args": ["-read-only"] }),
+            json!({
+                "read_only": true,
+                "binary": { "path": "github-mcp-server", "arguments": ["stdio", "--read-only=0"] },
+            }),
+            json!({ "read_only": true, "env": { "github_read_only": "false" } }),
+        ] {
+            assert!(
+                settings(value.clone()).validate_passthrough().is_err(),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_only_server_accepts_flags_that_keep_it_on() {
+        let settings = settings(json!({
+            "read_only": true,
+            "extra_args": ["--read-only", "--read-only=true", "--read-only-mode-docs"],
+        }));
+
+        assert!(settings.validate_passthrough().is_ok());
+    }
+
+    #[test]
+    fn read_only_flags_are_allowed_when_not_read_only() {
+        let settings = settings(json!({
+            "extra_args": ["--read-only", "--read-only-mode-docs"],
+            "env": { "GITHUB_READ_ONLY": "true" },
+        }));
+
+        assert!(settings.validate_passthrough().is_ok());
+    }
+
+    #[test]
+    fn active_profile_must_be_on_the_repository_host() {
+        let mut settings = with_work_profile("https://github.com/octocat/hello-world");
+        settings.active_profile = Some("work".into());
+
+        assert!(settings
+            .apply_profile()
+            .unwrap_err()
+            .contains("is on github.com, but the server is configured for github.example.com"));
+    }
+}
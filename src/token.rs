@@ -0,0 +1,155 @@
+//! Resolution of the GitHub token from the configured source.
+//!
+//! The extension runs in a WASI sandbox that sees neither the user's environment nor files
+//! outside its work directory, so environment variables, files and commands are resolved
+//! through host processes.
+
+use std::{env, fs};
+use zed_extension_api::{self as zed, process::Command, Result};
+
+/// Where the GitHub token is read from, in order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource<'a> {
+    /// `github_personal_access_token`, stored in plaintext in settings.
+    Plaintext(&'a str),
+    /// `token_env`, the name of an environment variable.
+    Env(&'a str),
+    /// `token_file`, a path to a file containing the token.
+    File(&'a str),
+    /// `token_command`, a shell command that prints the token.
+    Command(&'a str),
+}
+
+impl TokenSource<'_> {
+    /// Reads and trims the token, naming the source on failure.
+    pub fn resolve(&self) -> Result<String> {
+        let token = match self {
+            TokenSource::Plaintext(token) => Ok(token.to_string()),
+            TokenSource::Env(name) => read_env(name),
+            TokenSource::File(path) => read_file(path),
+            TokenSource::Command(command) => run_shell(command),
+        }
+        .map_err(|e| format!("failed to read GitHub token from {self}: {e}"))?;
+
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(format!("GitHub token from {self} is empty"));
+        }
+        Ok(token.to_string())
+    }
+}
+
+impl std::fmt::Display for TokenSource<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Plaintext(_) => write!(f, "`github_personal_access_token`"),
+            TokenSource::Env(name) => write!(f, "`token_env` ({name})"),
+            TokenSource::File(path) => write!(f, "`token_file` ({path})"),
+            TokenSource::Command(command) => write!(f, "`token_command` ({command})"),
+        }
+    }
+}
+
+fn read_env(name: &str) -> Result<String> {
+    // The name is interpolated into a `cmd` script on Windows, where `%` and `&` would let
+    // it run arbitrary commands.
+    if !is_env_var_name(name) {
+        return Err(format!(
+            "'{name}' is not a valid environment variable name, expected letters, digits and \
+             underscores, not starting with a digit"
+        ));
+    }
+    if let Ok(value) = env::var(name) {
+        return Ok(value);
+    }
+
+    let (platform, _) = zed::current_platform();
+    let output = match platform {
+        zed::Os::Windows => run_shell(&format!("echo %{name}%"))?,
+        zed::Os::Mac | zed::Os::Linux => run(Command::new("printenv").arg(name))
+            .map_err(|_| format!("environment variable {name} is not set"))?,
+    };
+    // `echo` leaves unset variables unexpanded.
+    if output.trim() == format!("%{name}%") {
+        return Err(format!("environment variable {name} is not set"));
+    }
+    Ok(output)
+}
+
+/// Whether `name` matches `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Reads a file on the host, expanding a leading `~/` to the home directory.
+pub fn read_file(path: &str) -> Result<String> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{rest}", read_env("HOME")?.trim()),
+        None => path.to_string(),
+    };
+    if let Ok(contents) = fs::read_to_string(&path) {
+        return Ok(contents);
+    }
+
+    let (platform, _) = zed::current_platform();
+    match platform {
+        zed::Os::Windows => run(Command::new("cmd").args(["/C", "type", &path])),
+        zed::Os::Mac | zed::Os::Linux => run(Command::new("cat").arg(&path)),
+    }
+}
+
+fn run_shell(script: &str) -> Result<String> {
+    let (platform, _) = zed::current_platform();
+    match platform {
+        zed::Os::Windows => run(Command::new("cmd").args(["/C", script])),
+        zed::Os::Mac | zed::Os::Linux => run(Command::new("sh").args(["-c", script])),
+    }
+}
+
+fn run(mut command: Command) -> Result<String> {
+    let output = command.output()?;
+    if output.status != Some(0) {
+        return Err(format!(
+            "`{}` exited with status {:?}: {}",
+            command.command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("output is not valid UTF-8: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_env_var_names() {
+        for name in ["GITHUB_TOKEN", "_token", "a1", "X"] {
+            assert!(is_env_var_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn rejects_env_var_names_that_a_shell_would_interpret() {
+        for name in [
+            "",
+            "1TOKEN",
+            "X%&calc&rem ",
+            "A B",
+            "$(id)",
+            "TOKEN;",
+            "TÖKEN",
+        ] {
+            let error = TokenSource::Env(name).resolve().unwrap_err();
+            assert!(
+                error.contains("is not a valid environment variable name"),
+                "{error}"
+            );
+        }
+    }
+}